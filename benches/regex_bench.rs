@@ -1,6 +1,10 @@
+use adam_regex::engine::compiler;
 use adam_regex::matcher::AdamRegex;
+use adam_regex::parser::parser::parse;
+use adam_regex::parser::Dialect;
+use adam_regex::set::RegexSet;
 use criterion::{criterion_group, criterion_main, Criterion};
-use regex::Regex as StdRegex;
+use regex::{Regex as StdRegex, RegexSet as StdRegexSet};
 use std::hint::black_box;
 
 fn bench_simple_repetition(c: &mut Criterion) {
@@ -73,6 +77,9 @@ fn bench_suffix_fail(c: &mut Criterion) {
     c.bench_function("suffix fail - adam", |b| {
         b.iter(|| adam.matches(black_box(&input)))
     });
+    c.bench_function("suffix fail search - adam", |b| {
+        b.iter(|| adam.is_match(black_box(&input)))
+    });
     c.bench_function("suffix fail - regex", |b| {
         b.iter(|| std.is_match(black_box(&input)))
     });
@@ -138,6 +145,155 @@ fn bench_mixed_dot_plus_optional(c: &mut Criterion) {
     });
 }
 
+fn bench_lazy_nth_from_end(c: &mut Criterion) {
+    let pattern = "(a|b)*a".to_string() + &"(a|b)".repeat(20);
+    let input = "ab".repeat(5000);
+
+    let adam = AdamRegex::builder().lazy(true).build(&pattern).unwrap();
+    let std = StdRegex::new(&pattern).unwrap();
+
+    c.bench_function("lazy nth from end - adam", |b| {
+        b.iter(|| adam.matches(black_box(&input)))
+    });
+    c.bench_function("lazy nth from end - regex", |b| {
+        b.iter(|| std.is_match(black_box(&input)))
+    });
+}
+
+fn bench_minimize_large_dfa(c: &mut Criterion) {
+    // The unminimized DFA has 2^15 + 1 states
+    let pattern = "(a|b)*a".to_string() + &"(a|b)".repeat(14);
+    let dfa = compiler::compile(&parse(&pattern), false);
+
+    c.bench_function("minimize large dfa - adam", |b| {
+        b.iter(|| black_box(&dfa).minimize())
+    });
+}
+
+fn bench_prefix_literal_search(c: &mut Criterion) {
+    let pattern = "ERROR: [a-z ]+";
+    let input = "INFO: all good\n".repeat(1000) + "ERROR: disk full";
+
+    let adam = AdamRegex::builder()
+        .dialect(Dialect::Ere)
+        .build(pattern)
+        .unwrap();
+    let std = StdRegex::new(pattern).unwrap();
+
+    c.bench_function("prefix literal search - adam", |b| {
+        b.iter(|| adam.find(black_box(&input)))
+    });
+    c.bench_function("prefix literal search - regex", |b| {
+        b.iter(|| std.find(black_box(&input)))
+    });
+}
+
+fn bench_accelerated_dot_star(c: &mut Criterion) {
+    let pattern = "a.*z";
+    let input = "a".to_string() + &"b".repeat(10000) + "z";
+
+    let adam = AdamRegex::from_str(pattern).unwrap();
+    let std = StdRegex::new("^a.*z$").unwrap();
+
+    c.bench_function("accelerated dot star - adam", |b| {
+        b.iter(|| adam.matches(black_box(&input)))
+    });
+    c.bench_function("accelerated dot star - regex", |b| {
+        b.iter(|| std.is_match(black_box(&input)))
+    });
+}
+
+fn bench_universal_accept(c: &mut Criterion) {
+    let pattern = "abc.*";
+    let input = "abc".to_string() + &"x".repeat(10000);
+
+    let adam = AdamRegex::from_str(pattern).unwrap();
+    let std = StdRegex::new("^abc.*$").unwrap();
+
+    c.bench_function("universal accept - adam", |b| {
+        b.iter(|| adam.matches(black_box(&input)))
+    });
+    c.bench_function("universal accept - regex", |b| {
+        b.iter(|| std.is_match(black_box(&input)))
+    });
+}
+
+fn bench_regex_set(c: &mut Criterion) {
+    let patterns: Vec<String> = (0..100).map(|i| format!("key{}(a|b)*", i)).collect();
+    let input = "key42".to_string() + &"ab".repeat(500);
+
+    let adam = RegexSet::new(&patterns).unwrap();
+    let std = StdRegexSet::new(patterns.iter().map(|p| format!("^{}$", p))).unwrap();
+
+    c.bench_function("regex set - adam", |b| {
+        b.iter(|| adam.matches(black_box(&input)))
+    });
+    c.bench_function("regex set - regex", |b| {
+        b.iter(|| std.matches(black_box(&input)))
+    });
+}
+
+fn bench_set_find_iter(c: &mut Criterion) {
+    let keywords = ["password", "secret", "token", "apikey", "private"];
+    let input = "the quick brown fox jumps over the lazy dog ".repeat(100) + "token secret";
+
+    let adam = RegexSet::new(keywords).unwrap();
+    let std = StdRegex::new(&keywords.join("|")).unwrap();
+
+    c.bench_function("set find iter - adam", |b| {
+        b.iter(|| adam.find_iter(black_box(&input)).count())
+    });
+    c.bench_function("set find iter - regex", |b| {
+        b.iter(|| std.find_iter(black_box(&input)).count())
+    });
+}
+
+fn bench_rfind_last_timestamp(c: &mut Criterion) {
+    let pattern = "[0-9]{2}:[0-9]{2}:[0-9]{2}";
+    let input = "12:00:01 start\n".to_string() + &"noise\n".repeat(1000) + "23:59:59 stop\n";
+
+    let adam = AdamRegex::builder()
+        .dialect(Dialect::Ere)
+        .build(pattern)
+        .unwrap();
+    let std = StdRegex::new(pattern).unwrap();
+
+    c.bench_function("rfind last timestamp - adam", |b| {
+        b.iter(|| adam.rfind(black_box(&input)))
+    });
+    c.bench_function("rfind last timestamp - regex", |b| {
+        b.iter(|| std.find_iter(black_box(&input)).last())
+    });
+}
+
+fn bench_overlapping(c: &mut Criterion) {
+    let pattern = "ab*a";
+    let input = "abbaabacab".repeat(1000);
+
+    let adam = AdamRegex::from_str(pattern).unwrap();
+
+    c.bench_function("overlapping - adam", |b| {
+        b.iter(|| adam.find_overlapping_iter(black_box(&input)).count())
+    });
+}
+
+fn bench_find_iter_reader(c: &mut Criterion) {
+    let pattern = "ERROR: [a-z ]+";
+    let input = "INFO: all good\nERROR: disk full\n".repeat(1000);
+
+    let adam = AdamRegex::builder()
+        .dialect(Dialect::Ere)
+        .build(pattern)
+        .unwrap();
+
+    c.bench_function("find iter reader - adam", |b| {
+        b.iter(|| adam.find_iter_reader(black_box(input.as_bytes())).count())
+    });
+    c.bench_function("find iter - adam", |b| {
+        b.iter(|| adam.find_iter(black_box(&input)).count())
+    });
+}
+
 criterion_group!(
     benches,
     bench_simple_repetition,
@@ -149,5 +305,15 @@ criterion_group!(
     bench_plus_repetition,
     bench_optional_char,
     bench_mixed_dot_plus_optional,
+    bench_lazy_nth_from_end,
+    bench_minimize_large_dfa,
+    bench_prefix_literal_search,
+    bench_accelerated_dot_star,
+    bench_universal_accept,
+    bench_regex_set,
+    bench_set_find_iter,
+    bench_rfind_last_timestamp,
+    bench_overlapping,
+    bench_find_iter_reader,
 );
 criterion_main!(benches);
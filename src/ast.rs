@@ -2,7 +2,9 @@ use std::fmt;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Regex {
+    Empty,
     Byte(u8),
+    Class(Vec<(u8, u8)>),
     Dot,
     Star(Box<Regex>),
     Plus(Box<Regex>),
@@ -11,6 +13,28 @@ pub enum Regex {
     Alt(Box<Regex>, Box<Regex>),
 }
 
+impl Regex {
+    /// Counts the nodes in the tree without recursing.
+    pub fn node_count(&self) -> usize {
+        let mut count = 0;
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            count += 1;
+            match node {
+                Regex::Empty | Regex::Byte(_) | Regex::Class(_) | Regex::Dot => {}
+                Regex::Star(inner) | Regex::Plus(inner) | Regex::Optional(inner) => {
+                    stack.push(inner)
+                }
+                Regex::Concat(left, right) | Regex::Alt(left, right) => {
+                    stack.push(left);
+                    stack.push(right);
+                }
+            }
+        }
+        count
+    }
+}
+
 impl fmt::Display for Regex {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fn needs_parens(inner: &Regex, outer_prec: u8) -> bool {
@@ -21,13 +45,14 @@ impl fmt::Display for Regex {
                 Regex::Plus(_) => 4,
                 Regex::Optional(_) => 5,
                 Regex::Dot => 6,
-                Regex::Byte(_) => 7,
+                Regex::Byte(_) | Regex::Class(_) | Regex::Empty => 7,
             };
             inner_prec < outer_prec
         }
 
         fn write_expr(expr: &Regex, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             match expr {
+                Regex::Empty => write!(f, "()"),
                 Regex::Byte(b) => {
                     let c = *b as char;
                     if c.is_ascii_graphic() && !"()*|".contains(c) {
@@ -36,6 +61,26 @@ impl fmt::Display for Regex {
                         write!(f, "\\x{:02X}", b)
                     }
                 }
+                Regex::Class(ranges) => {
+                    fn write_byte(b: u8, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                        let c = b as char;
+                        if c.is_ascii_alphanumeric() {
+                            write!(f, "{}", c)
+                        } else {
+                            write!(f, "\\x{:02X}", b)
+                        }
+                    }
+
+                    write!(f, "[")?;
+                    for &(lo, hi) in ranges {
+                        write_byte(lo, f)?;
+                        if hi != lo {
+                            write!(f, "-")?;
+                            write_byte(hi, f)?;
+                        }
+                    }
+                    write!(f, "]")
+                }
                 Regex::Star(inner) => {
                     if needs_parens(inner, 3) {
                         write!(f, "({})*", inner)
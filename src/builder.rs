@@ -0,0 +1,233 @@
+use std::ops::{Bound, RangeBounds, RangeInclusive};
+
+use crate::{ast::Regex, utf8};
+
+impl Regex {
+    /// Matches `text` exactly. Nothing in it is treated as syntax, and
+    /// non-ASCII chars are lowered to their UTF-8 bytes.
+    pub fn literal(text: &str) -> Regex {
+        concat_all(text.bytes().map(Regex::Byte))
+    }
+
+    /// Like `literal`, but each char also matches its other case. Chars whose
+    /// case mapping is not a single char (such as `ß`) match only themselves.
+    pub fn literal_ignore_case(text: &str) -> Regex {
+        concat_all(text.chars().map(|c| {
+            let lower: Vec<char> = c.to_lowercase().collect();
+            let upper: Vec<char> = c.to_uppercase().collect();
+            match (lower.as_slice(), upper.as_slice()) {
+                ([lower], [upper]) if lower != upper => {
+                    Regex::class_of([*lower..=*lower, *upper..=*upper])
+                }
+                _ => Regex::from(c),
+            }
+        }))
+    }
+
+    /// Matches any one of `items`, e.g. `Regex::any_of(["GET", "POST"])`.
+    /// An empty list matches nothing.
+    pub fn any_of<I>(items: I) -> Regex
+    where
+        I: IntoIterator,
+        I::Item: Into<Regex>,
+    {
+        alt_all(items.into_iter().map(Into::into))
+    }
+
+    /// Matches a single char in `range`, encoded as UTF-8.
+    pub fn class(range: RangeInclusive<char>) -> Regex {
+        Regex::class_of([range])
+    }
+
+    /// Matches a single char in any of `ranges`, encoded as UTF-8.
+    pub fn class_of<I>(ranges: I) -> Regex
+    where
+        I: IntoIterator<Item = RangeInclusive<char>>,
+    {
+        let mut ascii = Vec::new();
+        let mut multibyte = Vec::new();
+
+        for range in ranges {
+            for seq in utf8::sequences(*range.start(), *range.end()) {
+                if seq.len() == 1 {
+                    ascii.push(seq[0]);
+                } else {
+                    multibyte.push(concat_all(
+                        seq.into_iter()
+                            .map(|(lo, hi)| Regex::byte_class([(lo, hi)])),
+                    ));
+                }
+            }
+        }
+
+        let ascii = if ascii.is_empty() {
+            None
+        } else {
+            Some(Regex::byte_class(ascii))
+        };
+        alt_all(ascii.into_iter().chain(multibyte))
+    }
+
+    /// Matches a single char outside all of `ranges`, encoded as UTF-8.
+    pub fn class_except<I>(ranges: I) -> Regex
+    where
+        I: IntoIterator<Item = RangeInclusive<char>>,
+    {
+        let mut excluded: Vec<(u32, u32)> = ranges
+            .into_iter()
+            .map(|r| (*r.start() as u32, *r.end() as u32))
+            .filter(|(lo, hi)| lo <= hi)
+            .collect();
+        excluded.sort_unstable();
+
+        let mut gaps = Vec::new();
+        let mut next = 0u32;
+        for (lo, hi) in excluded {
+            if lo > next {
+                gaps.push((next, lo - 1));
+            }
+            next = next.max(hi + 1);
+        }
+        if next <= char::MAX as u32 {
+            gaps.push((next, char::MAX as u32));
+        }
+
+        // Gaps may start or end inside the surrogates, which are not chars.
+        Regex::class_of(gaps.into_iter().flat_map(|(lo, hi)| {
+            [(lo, hi.min(0xD7FF)), (lo.max(0xE000), hi)]
+                .into_iter()
+                .filter(|(lo, hi)| lo <= hi)
+                .filter_map(|(lo, hi)| Some(char::from_u32(lo)?..=char::from_u32(hi)?))
+        }))
+    }
+
+    /// Matches any single UTF-8 encoded char, unlike `Dot` which matches
+    /// any single byte.
+    pub fn any_char() -> Regex {
+        Regex::class('\0'..=char::MAX)
+    }
+
+    /// Matches a single byte in any of the inclusive `ranges`.
+    pub fn byte_class<I>(ranges: I) -> Regex
+    where
+        I: IntoIterator<Item = (u8, u8)>,
+    {
+        let mut ranges: Vec<(u8, u8)> = ranges.into_iter().filter(|(lo, hi)| lo <= hi).collect();
+        ranges.sort_unstable();
+
+        let mut merged: Vec<(u8, u8)> = Vec::with_capacity(ranges.len());
+        for (lo, hi) in ranges {
+            match merged.last_mut() {
+                Some(last) if lo as u16 <= last.1 as u16 + 1 => last.1 = last.1.max(hi),
+                _ => merged.push((lo, hi)),
+            }
+        }
+
+        match merged.as_slice() {
+            [(lo, hi)] if lo == hi => Regex::Byte(*lo),
+            _ => Regex::Class(merged),
+        }
+    }
+
+    /// Matches nothing, not even the empty string.
+    pub fn never() -> Regex {
+        Regex::Class(vec![])
+    }
+
+    pub fn then(self, next: impl Into<Regex>) -> Regex {
+        Regex::Concat(Box::new(self), Box::new(next.into()))
+    }
+
+    pub fn or(self, other: impl Into<Regex>) -> Regex {
+        Regex::Alt(Box::new(self), Box::new(other.into()))
+    }
+
+    pub fn star(self) -> Regex {
+        Regex::Star(Box::new(self))
+    }
+
+    pub fn plus(self) -> Regex {
+        Regex::Plus(Box::new(self))
+    }
+
+    pub fn optional(self) -> Regex {
+        Regex::Optional(Box::new(self))
+    }
+
+    /// Matches between `min` and `max` copies of `self`, e.g. `repeat(2..=4)`
+    /// or `repeat(1..)`. Counted repetition is expanded into copies of the
+    /// expression, so large bounds produce large automata.
+    pub fn repeat(self, range: impl RangeBounds<usize>) -> Regex {
+        let min = match range.start_bound() {
+            Bound::Included(&n) => n,
+            Bound::Excluded(&n) => n + 1,
+            Bound::Unbounded => 0,
+        };
+        let max = match range.end_bound() {
+            Bound::Included(&n) => Some(n),
+            Bound::Excluded(&n) => Some(n.checked_sub(1).expect("empty repetition range")),
+            Bound::Unbounded => None,
+        };
+
+        match max {
+            None if min == 0 => self.star(),
+            None => concat_all(std::iter::repeat_n(self.clone(), min - 1).chain([self.plus()])),
+            Some(max) => {
+                assert!(
+                    min <= max,
+                    "repetition minimum {} exceeds maximum {}",
+                    min,
+                    max
+                );
+
+                // x{2,4} becomes xxx?x?, which keeps the tree shallow.
+                let optional = self.clone().optional();
+                concat_all(
+                    std::iter::repeat_n(self, min).chain(std::iter::repeat_n(optional, max - min)),
+                )
+            }
+        }
+    }
+}
+
+impl From<&str> for Regex {
+    fn from(text: &str) -> Self {
+        Regex::literal(text)
+    }
+}
+
+impl From<char> for Regex {
+    fn from(c: char) -> Self {
+        Regex::literal(c.encode_utf8(&mut [0; 4]))
+    }
+}
+
+pub fn literal(text: &str) -> Regex {
+    Regex::literal(text)
+}
+
+pub fn any_of<I>(items: I) -> Regex
+where
+    I: IntoIterator,
+    I::Item: Into<Regex>,
+{
+    Regex::any_of(items)
+}
+
+pub fn class(range: RangeInclusive<char>) -> Regex {
+    Regex::class(range)
+}
+
+fn concat_all(items: impl IntoIterator<Item = Regex>) -> Regex {
+    items
+        .into_iter()
+        .reduce(|acc, next| acc.then(next))
+        .unwrap_or(Regex::Empty)
+}
+
+fn alt_all(items: impl IntoIterator<Item = Regex>) -> Regex {
+    items
+        .into_iter()
+        .reduce(|acc, next| acc.or(next))
+        .unwrap_or_else(Regex::never)
+}
@@ -0,0 +1,161 @@
+use crate::engine::glushkov::Glushkov;
+
+/// The most positions a pattern may have to run bit-parallel.
+pub const MAX_POSITIONS: usize = 64;
+
+/// Simulates the Glushkov automaton of a small pattern with one bit per
+/// position, in the style of Shift-And. Each byte costs one table lookup
+/// per eight positions, with nothing to build beyond those tables.
+#[derive(Debug)]
+pub struct BitParallel {
+    /// The positions that match each byte.
+    masks: Box<[u64; 256]>,
+    /// `follow[i][bits]` is the union of the follow sets of positions
+    /// `8 * i + j` for each bit `j` set in `bits`.
+    follow: Vec<[u64; 256]>,
+    first: u64,
+    last: u64,
+    nullable: bool,
+}
+
+fn mask(positions: &[usize]) -> u64 {
+    positions.iter().fold(0, |acc, &p| acc | 1 << p)
+}
+
+impl BitParallel {
+    /// Returns `None` if the pattern has more than `MAX_POSITIONS`
+    /// positions.
+    pub(crate) fn new(glushkov: &Glushkov) -> Option<Self> {
+        let positions = glushkov.positions.len();
+        if positions > MAX_POSITIONS {
+            return None;
+        }
+
+        let mut masks = Box::new([0; 256]);
+        for (p, ranges) in glushkov.positions.iter().enumerate() {
+            for &(lo, hi) in ranges {
+                for m in &mut masks[lo as usize..=hi as usize] {
+                    *m |= 1 << p;
+                }
+            }
+        }
+
+        let follow = (0..positions.div_ceil(8))
+            .map(|chunk| {
+                let mut table = [0; 256];
+                for (bits, entry) in table.iter_mut().enumerate() {
+                    for j in 0..8 {
+                        let p = 8 * chunk + j;
+                        if bits >> j & 1 == 1 && p < positions {
+                            *entry |= mask(&glushkov.follow[p]);
+                        }
+                    }
+                }
+                table
+            })
+            .collect();
+
+        Some(Self {
+            masks,
+            follow,
+            first: mask(&glushkov.first),
+            last: mask(&glushkov.last),
+            nullable: glushkov.nullable,
+        })
+    }
+
+    /// Length of the longest prefix of `input` that matches.
+    pub fn longest_match(&self, input: &[u8]) -> Option<usize> {
+        let mut last = self.nullable.then_some(0);
+        let Some((&first, rest)) = input.split_first() else {
+            return last;
+        };
+
+        let mut active = self.first & self.masks[first as usize];
+        for (i, &b) in rest.iter().enumerate() {
+            if active & self.last != 0 {
+                last = Some(i + 1);
+            }
+            if active == 0 {
+                return last;
+            }
+            active = self.step(active) & self.masks[b as usize];
+        }
+
+        if active & self.last != 0 {
+            last = Some(input.len());
+        }
+        last
+    }
+
+    /// Length of the shortest prefix of `input` that matches, found by
+    /// stopping at the first step with a last position active.
+    pub fn shortest_match(&self, input: &[u8]) -> Option<usize> {
+        if self.nullable {
+            return Some(0);
+        }
+        let mut active = self.first;
+        for (i, &b) in input.iter().enumerate() {
+            if i > 0 {
+                active = self.step(active);
+            }
+            active &= self.masks[b as usize];
+            if active & self.last != 0 {
+                return Some(i + 1);
+            }
+            if active == 0 {
+                return None;
+            }
+        }
+        None
+    }
+
+    /// The active positions after reading `input` on from `active`, where
+    /// `None` stands for nothing read yet.
+    pub(crate) fn feed(&self, active: Option<u64>, input: &[u8]) -> Option<u64> {
+        let mut bytes = input.iter();
+        let mut active = match active {
+            Some(active) => active,
+            None => self.first & self.masks[*bytes.next()? as usize],
+        };
+        for &b in bytes {
+            if active == 0 {
+                break;
+            }
+            active = self.step(active) & self.masks[b as usize];
+        }
+        Some(active)
+    }
+
+    /// Whether the input that led to `active`, as given by `feed`, matches.
+    pub(crate) fn is_accepting(&self, active: Option<u64>) -> bool {
+        match active {
+            Some(active) => active & self.last != 0,
+            None => self.nullable,
+        }
+    }
+
+    fn step(&self, active: u64) -> u64 {
+        let mut next = 0;
+        for (i, table) in self.follow.iter().enumerate() {
+            next |= table[(active >> (8 * i)) as u8 as usize];
+        }
+        next
+    }
+
+    pub fn matches(&self, input: &str) -> bool {
+        let Some((&first, rest)) = input.as_bytes().split_first() else {
+            return self.nullable;
+        };
+
+        let mut active = self.first & self.masks[first as usize];
+        for &b in rest {
+            if active == 0 {
+                return false;
+            }
+            active = self.step(active) & self.masks[b as usize];
+        }
+
+        active & self.last != 0
+    }
+}
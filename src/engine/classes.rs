@@ -0,0 +1,110 @@
+/// A partition of the 256 byte values into equivalence classes. Two bytes
+/// share a class when no transition in the automaton tells them apart, so
+/// transition tables only need one column per class.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ByteClasses {
+    map: [u8; 256],
+    count: usize,
+}
+
+impl Default for ByteClasses {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ByteClasses {
+    /// Every byte in one class.
+    pub fn new() -> Self {
+        Self {
+            map: [0; 256],
+            count: 1,
+        }
+    }
+
+    /// Refines the classes so that the bytes in `set` are separated from
+    /// the bytes outside it.
+    pub fn split(&mut self, set: &[bool; 256]) {
+        let mut remap: Vec<[Option<u8>; 2]> = vec![[None; 2]; self.count];
+        let mut count = 0;
+        for (b, class) in self.map.iter_mut().enumerate() {
+            let slot = &mut remap[*class as usize][set[b] as usize];
+            *class = *slot.get_or_insert_with(|| {
+                count += 1;
+                (count - 1) as u8
+            });
+        }
+        self.count = count;
+    }
+
+    pub fn get(&self, byte: u8) -> usize {
+        self.map[byte as usize] as usize
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// One byte from each class, in class order.
+    pub fn representatives(&self) -> Vec<u8> {
+        let mut reps = vec![None; self.count];
+        for b in 0..=255u8 {
+            reps[self.get(b)].get_or_insert(b);
+        }
+        reps.into_iter().flatten().collect()
+    }
+}
+
+#[cfg(test)]
+mod classes_tests {
+    use super::*;
+
+    fn set(bytes: &[u8]) -> [bool; 256] {
+        let mut set = [false; 256];
+        for &b in bytes {
+            set[b as usize] = true;
+        }
+        set
+    }
+
+    #[test]
+    fn new_is_a_single_class() {
+        let classes = ByteClasses::new();
+        assert_eq!(classes.count(), 1);
+        assert_eq!(classes.get(0), classes.get(255));
+        assert_eq!(classes.representatives(), vec![0]);
+    }
+
+    #[test]
+    fn split_separates_members() {
+        let mut classes = ByteClasses::new();
+        classes.split(&set(b"ab"));
+        assert_eq!(classes.count(), 2);
+        assert_eq!(classes.get(b'a'), classes.get(b'b'));
+        assert_ne!(classes.get(b'a'), classes.get(b'c'));
+        assert_eq!(classes.get(b'c'), classes.get(b'z'));
+    }
+
+    #[test]
+    fn overlapping_splits_refine() {
+        let mut classes = ByteClasses::new();
+        classes.split(&set(b"abc"));
+        classes.split(&set(b"cd"));
+        // {a, b}, {c}, {d}, everything else
+        assert_eq!(classes.count(), 4);
+        assert_eq!(classes.get(b'a'), classes.get(b'b'));
+        assert_ne!(classes.get(b'b'), classes.get(b'c'));
+        assert_ne!(classes.get(b'c'), classes.get(b'd'));
+        assert_eq!(classes.representatives(), vec![0, b'a', b'c', b'd']);
+    }
+
+    #[test]
+    fn every_byte_can_be_its_own_class() {
+        let mut classes = ByteClasses::new();
+        for b in 0..=255u8 {
+            classes.split(&set(&[b]));
+        }
+        assert_eq!(classes.count(), 256);
+        assert_eq!(classes.representatives().len(), 256);
+    }
+}
@@ -1,12 +1,157 @@
 use crate::ast::Regex;
+use crate::engine::bitparallel::BitParallel;
 use crate::engine::dfa::DFA;
-use crate::engine::nfa::from_regex;
+use crate::engine::glushkov::Glushkov;
+use crate::engine::lazy::LazyDFA;
+use crate::engine::nfa::{from_regex, from_regexes, NFA};
+use crate::error::{Error, Limits};
+
+/// How the AST is turned into an NFA before determinization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Construction {
+    /// Thompson's construction, linear in the pattern but with many
+    /// epsilon edges.
+    #[default]
+    Thompson,
+    /// The Glushkov position automaton: one state per byte, class or dot
+    /// and no epsilon edges between them. It can have quadratically many
+    /// edges, but subset construction over it is usually faster.
+    Glushkov,
+}
+
+fn build_nfa(ast: &Regex, construction: Construction) -> NFA {
+    match construction {
+        Construction::Thompson => from_regex(ast),
+        Construction::Glushkov => Glushkov::new(ast).to_nfa(),
+    }
+}
+
+/// Builds the NFA alone, for simulation or later determinization.
+pub fn compile_nfa(ast: &Regex, construction: Construction, limits: &Limits) -> Result<NFA, Error> {
+    check_ast(ast, limits)?;
+
+    let nfa = build_nfa(ast, construction);
+    if nfa.states.len() > limits.max_nfa_states {
+        return Err(Error::TooManyNfaStates {
+            limit: limits.max_nfa_states,
+        });
+    }
+    Ok(nfa)
+}
 
 pub fn compile(ast: &Regex, minimize: bool) -> DFA {
-    let nfa = from_regex(&ast);
+    let nfa = from_regex(ast);
     let dfa = nfa.to_dfa();
     if minimize {
         return dfa.minimize();
     }
     dfa
 }
+
+pub fn compile_with_limits(ast: &Regex, minimize: bool, limits: &Limits) -> Result<DFA, Error> {
+    compile_using(ast, Construction::Thompson, minimize, limits)
+}
+
+pub fn compile_using(
+    ast: &Regex,
+    construction: Construction,
+    minimize: bool,
+    limits: &Limits,
+) -> Result<DFA, Error> {
+    let nfa = compile_nfa(ast, construction, limits)?;
+    let dfa = nfa.try_to_dfa(limits.max_dfa_states)?;
+    if minimize {
+        return Ok(dfa.minimize());
+    }
+    Ok(dfa)
+}
+
+/// Builds a minimized DFA for the reversed language of `ast`, from its NFA
+/// with every edge turned around. `DFA::longest_match_rev` runs it from the
+/// end of the input towards the start.
+pub fn compile_reverse(ast: &Regex, limits: &Limits) -> Result<DFA, Error> {
+    let nfa = compile_nfa(ast, Construction::Thompson, limits)?.reverse();
+    Ok(nfa.try_to_dfa(limits.max_dfa_states)?.minimize())
+}
+
+/// Builds one minimized DFA for several patterns, whose states record
+/// which of them accept. Limits apply to each AST and to the combined
+/// automata.
+pub fn compile_set(asts: &[Regex], limits: &Limits) -> Result<DFA, Error> {
+    for ast in asts {
+        check_ast(ast, limits)?;
+    }
+    let nfa = from_regexes(asts);
+    if nfa.states.len() > limits.max_nfa_states {
+        return Err(Error::TooManyNfaStates {
+            limit: limits.max_nfa_states,
+        });
+    }
+    Ok(nfa.try_to_dfa(limits.max_dfa_states)?.minimize())
+}
+
+/// Builds only the NFA up front and leaves determinization to match time.
+/// `Limits::max_dfa_states` bounds the lazy DFA's cache instead of failing
+/// compilation.
+pub fn compile_lazy(
+    ast: &Regex,
+    construction: Construction,
+    limits: &Limits,
+) -> Result<LazyDFA, Error> {
+    let nfa = compile_nfa(ast, construction, limits)?;
+    Ok(LazyDFA::new(nfa, limits.max_dfa_states))
+}
+
+/// Builds a bit-parallel simulation of the pattern, or returns `None` if
+/// it has too many positions for one.
+pub fn compile_bit_parallel(ast: &Regex, limits: &Limits) -> Result<Option<BitParallel>, Error> {
+    check_ast(ast, limits)?;
+    Ok(BitParallel::new(&Glushkov::new(ast)))
+}
+
+/// Walks the AST without recursion, so that an over-deep tree is rejected
+/// before anything recurses into it. Depth is counted the same way as in
+/// the parser, with runs of `Concat` or `Alt` forming a single level.
+pub fn check_ast(ast: &Regex, limits: &Limits) -> Result<(), Error> {
+    let mut nodes = 0;
+    let mut stack = vec![(ast, 1)];
+
+    while let Some((node, depth)) = stack.pop() {
+        nodes += 1;
+        if nodes > limits.max_ast_nodes {
+            return Err(Error::TooManyAstNodes {
+                limit: limits.max_ast_nodes,
+            });
+        }
+        if depth > limits.max_depth {
+            return Err(Error::NestingTooDeep {
+                limit: limits.max_depth,
+            });
+        }
+
+        match node {
+            Regex::Empty | Regex::Byte(_) | Regex::Class(_) | Regex::Dot => {}
+            Regex::Star(inner) | Regex::Plus(inner) | Regex::Optional(inner) => {
+                stack.push((inner, depth + 1));
+            }
+            Regex::Concat(left, right) => {
+                let left_depth = match **left {
+                    Regex::Concat(_, _) => depth,
+                    _ => depth + 1,
+                };
+                stack.push((left, left_depth));
+                stack.push((right, depth + 1));
+            }
+            Regex::Alt(left, right) => {
+                let left_depth = match **left {
+                    Regex::Alt(_, _) => depth,
+                    _ => depth + 1,
+                };
+                stack.push((left, left_depth));
+                stack.push((right, depth + 1));
+            }
+        }
+    }
+
+    Ok(())
+}
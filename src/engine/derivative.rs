@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::{
+    ast::Regex,
+    engine::{
+        classes::ByteClasses,
+        dfa::{DEAD, DFA},
+    },
+};
+
+/// A regex kept in a normal form, so that derivatives which only differ by
+/// the similarity rules (`r|r = r`, `r|s = s|r`, `(r|s)|t = r|(s|t)`, and
+/// identities for the empty string and the empty language) compare equal.
+/// That is enough for the set of derivatives to be finite.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Term {
+    Never,
+    Epsilon,
+    /// Any byte in one of the ranges.
+    Set(Vec<(u8, u8)>),
+    Concat(Rc<Term>, Rc<Term>),
+    Star(Rc<Term>),
+    /// Sorted, without duplicates, with at least two members and none of
+    /// them `Never` or another `Alt`.
+    Alt(Vec<Rc<Term>>),
+}
+
+fn never() -> Rc<Term> {
+    Rc::new(Term::Never)
+}
+
+fn epsilon() -> Rc<Term> {
+    Rc::new(Term::Epsilon)
+}
+
+fn concat(a: Rc<Term>, b: Rc<Term>) -> Rc<Term> {
+    match (&*a, &*b) {
+        (Term::Never, _) | (_, Term::Never) => never(),
+        (Term::Epsilon, _) => b,
+        (_, Term::Epsilon) => a,
+        (Term::Concat(x, y), _) => concat(x.clone(), concat(y.clone(), b)),
+        _ => Rc::new(Term::Concat(a, b)),
+    }
+}
+
+fn alt(terms: impl IntoIterator<Item = Rc<Term>>) -> Rc<Term> {
+    let mut members = Vec::new();
+    for term in terms {
+        match &*term {
+            Term::Never => {}
+            Term::Alt(inner) => members.extend(inner.iter().cloned()),
+            _ => members.push(term),
+        }
+    }
+    members.sort();
+    members.dedup();
+    match members.len() {
+        0 => never(),
+        1 => members.pop().unwrap(),
+        _ => Rc::new(Term::Alt(members)),
+    }
+}
+
+fn star(a: Rc<Term>) -> Rc<Term> {
+    match &*a {
+        Term::Never | Term::Epsilon => epsilon(),
+        Term::Star(_) => a,
+        _ => Rc::new(Term::Star(a)),
+    }
+}
+
+fn lower(regex: &Regex) -> Rc<Term> {
+    match regex {
+        Regex::Empty => epsilon(),
+        Regex::Byte(b) => Rc::new(Term::Set(vec![(*b, *b)])),
+        Regex::Class(ranges) if ranges.is_empty() => never(),
+        Regex::Class(ranges) => Rc::new(Term::Set(ranges.clone())),
+        Regex::Dot => Rc::new(Term::Set(vec![(0, 255)])),
+        Regex::Concat(a, b) => concat(lower(a), lower(b)),
+        Regex::Alt(a, b) => alt([lower(a), lower(b)]),
+        Regex::Star(a) => star(lower(a)),
+        Regex::Plus(a) => {
+            let a = lower(a);
+            concat(a.clone(), star(a))
+        }
+        Regex::Optional(a) => alt([lower(a), epsilon()]),
+    }
+}
+
+fn nullable(term: &Term) -> bool {
+    match term {
+        Term::Never | Term::Set(_) => false,
+        Term::Epsilon | Term::Star(_) => true,
+        Term::Concat(a, b) => nullable(a) && nullable(b),
+        Term::Alt(members) => members.iter().any(|m| nullable(m)),
+    }
+}
+
+/// The term matching every `s` such that `term` matches `byte` followed
+/// by `s`.
+fn derive(term: &Rc<Term>, byte: u8) -> Rc<Term> {
+    match &**term {
+        Term::Never | Term::Epsilon => never(),
+        Term::Set(ranges) => {
+            if ranges.iter().any(|&(lo, hi)| lo <= byte && byte <= hi) {
+                epsilon()
+            } else {
+                never()
+            }
+        }
+        Term::Concat(a, b) => {
+            let left = concat(derive(a, byte), b.clone());
+            if nullable(a) {
+                alt([left, derive(b, byte)])
+            } else {
+                left
+            }
+        }
+        Term::Star(a) => concat(derive(a, byte), term.clone()),
+        Term::Alt(members) => alt(members.iter().map(|m| derive(m, byte))),
+    }
+}
+
+fn byte_classes(term: &Term, classes: &mut ByteClasses) {
+    match term {
+        Term::Never | Term::Epsilon => {}
+        Term::Set(ranges) => {
+            let mut set = [false; 256];
+            for &(lo, hi) in ranges {
+                set[lo as usize..=hi as usize].fill(true);
+            }
+            classes.split(&set);
+        }
+        Term::Concat(a, b) => {
+            byte_classes(a, classes);
+            byte_classes(b, classes);
+        }
+        Term::Star(a) => byte_classes(a, classes),
+        Term::Alt(members) => members.iter().for_each(|m| byte_classes(m, classes)),
+    }
+}
+
+/// Builds a DFA straight from the AST with Brzozowski derivatives: each
+/// state is a normalized term, and its successor on a byte class is the
+/// derivative by one byte of that class. This shares nothing with the
+/// Thompson, subset and Hopcroft pipeline, so the two can check each
+/// other. The result is not minimized.
+pub fn compile(ast: &Regex) -> DFA {
+    let start = lower(ast);
+    let mut classes = ByteClasses::new();
+    byte_classes(&start, &mut classes);
+    let representatives = classes.representatives();
+
+    let mut ids = HashMap::new();
+    let mut terms = vec![never()];
+    ids.insert(never(), DEAD);
+
+    let start_id = *ids.entry(start.clone()).or_insert_with(|| {
+        terms.push(start);
+        terms.len() - 1
+    });
+
+    let mut rows = Vec::new();
+    let mut patterns = Vec::new();
+    while rows.len() < terms.len() {
+        let id = rows.len();
+        let term = terms[id].clone();
+        patterns.push(if nullable(&term) { vec![0] } else { vec![] });
+
+        let row = representatives
+            .iter()
+            .map(|&byte| {
+                let next = derive(&term, byte);
+                *ids.entry(next.clone()).or_insert_with(|| {
+                    terms.push(next);
+                    terms.len() - 1
+                })
+            })
+            .collect();
+        rows.push(row);
+    }
+
+    DFA::from_rows(&rows, classes, start_id, patterns)
+}
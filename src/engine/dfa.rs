@@ -1,138 +1,698 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::HashMap;
 
 use bit_set::BitSet;
 
+use crate::engine::classes::ByteClasses;
+
+/// Index of the dead state. Every DFA has one: it never accepts and every
+/// transition out of it loops back to itself, so no transition is missing.
+pub const DEAD: usize = 0;
+
 #[derive(Debug)]
 pub struct DFA {
-    pub states: Vec<[Option<usize>; 256]>,
-    pub start: usize,
+    /// Transitions for all states in one contiguous table, `stride` entries
+    /// (one per byte class) per state. Entries are premultiplied: they hold
+    /// the offset of the target's row rather than its index, so stepping is
+    /// a single load.
+    table: Vec<u32>,
+    stride: usize,
+    pub classes: ByteClasses,
+    start: u32,
+    /// Accepting states, by index.
     pub accepting: BitSet,
+    /// The distinct sets of pattern IDs that states accept, the empty set
+    /// first. A DFA built from one pattern only ever has pattern 0.
+    pattern_sets: Vec<Vec<usize>>,
+    /// For each state index, its set in `pattern_sets`.
+    set_of: Vec<u32>,
+    /// For each state index, how to skip over the bytes it loops on.
+    accel: Vec<Option<Accel>>,
+    kinds: Vec<StateKind>,
 }
 
-impl DFA {
-    fn refine(&self, partition: &mut Vec<BitSet>, state_to_group: &mut Vec<usize>, num_states: usize) {
-        let alphabet: Vec<u8> = (0u8..=255).collect();
+/// Which match wins when several patterns match at the same start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Priority {
+    /// The longest match, and of those the lowest pattern ID.
+    #[default]
+    Longest,
+    /// The lowest pattern ID that matches at all, with its longest match.
+    FirstPattern,
+}
 
-        let mut worklist: VecDeque<(usize, u8)> = VecDeque::new();
-        for (i, _) in partition.iter().enumerate() {
-            for &c in &alphabet {
-                worklist.push_back((i, c));
+impl Priority {
+    /// Folds an accepting state's patterns `set`, reached at `end`, into
+    /// the best `(pattern, end)` so far.
+    fn prefer(
+        self,
+        best: Option<(usize, usize)>,
+        set: &[usize],
+        end: usize,
+    ) -> Option<(usize, usize)> {
+        let Some(&first) = set.first() else {
+            return best;
+        };
+        match (self, best) {
+            (Priority::Longest, _) | (_, None) => Some((first, end)),
+            (Priority::FirstPattern, Some((p, _))) if first < p => Some((first, end)),
+            (Priority::FirstPattern, Some((p, _))) if set.binary_search(&p).is_ok() => {
+                Some((p, end))
             }
+            (Priority::FirstPattern, best) => best,
         }
+    }
+}
+
+/// What a state says about the rest of the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateKind {
+    /// No continuation accepts.
+    Dead,
+    /// Every continuation accepts.
+    Universal,
+    Normal,
+}
+
+/// The one to three bytes that leave a state which loops to itself on
+/// every other byte. Matching in such a state can jump straight to the
+/// next of them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Accel {
+    One(u8),
+    Two(u8, u8),
+    Three(u8, u8, u8),
+}
+
+impl Accel {
+    /// Position of the first escaping byte at or after `at`, or the end
+    /// of `haystack`.
+    fn skip(&self, haystack: &[u8], at: usize) -> usize {
+        let rest = &haystack[at..];
+        let found = match *self {
+            Accel::One(a) => memchr::memchr(a, rest),
+            Accel::Two(a, b) => memchr::memchr2(a, b, rest),
+            Accel::Three(a, b, c) => memchr::memchr3(a, b, c, rest),
+        };
+        found.map_or(haystack.len(), |i| at + i)
+    }
+
+    /// Like `skip` but reading `haystack[..end]` backwards: the position
+    /// just past the last escaping byte, or 0.
+    fn skip_rev(&self, haystack: &[u8], end: usize) -> usize {
+        let rest = &haystack[..end];
+        let found = match *self {
+            Accel::One(a) => memchr::memrchr(a, rest),
+            Accel::Two(a, b) => memchr::memrchr2(a, b, rest),
+            Accel::Three(a, b, c) => memchr::memrchr3(a, b, c, rest),
+        };
+        found.map_or(0, |i| i + 1)
+    }
+}
+
+impl DFA {
+    /// Builds a DFA from one row of target indices per state and the
+    /// patterns each state accepts. Row `DEAD` must be the dead state.
+    pub(crate) fn from_rows(
+        rows: &[Vec<usize>],
+        classes: ByteClasses,
+        start: usize,
+        patterns: Vec<Vec<usize>>,
+    ) -> DFA {
+        let mut pattern_sets = vec![Vec::new()];
+        let mut ids = HashMap::from([(Vec::new(), 0)]);
+        let set_of = patterns
+            .into_iter()
+            .map(|set| {
+                *ids.entry(set).or_insert_with_key(|set| {
+                    pattern_sets.push(set.clone());
+                    pattern_sets.len() as u32 - 1
+                })
+            })
+            .collect();
+        DFA::with_sets(rows, classes, start, pattern_sets, set_of)
+    }
 
-        while let Some((group_idx, c)) = worklist.pop_front() {
-            let mut involved: HashMap<usize, BitSet> = HashMap::new();
+    /// Like `from_rows`, with the pattern sets already deduplicated.
+    fn with_sets(
+        rows: &[Vec<usize>],
+        classes: ByteClasses,
+        start: usize,
+        pattern_sets: Vec<Vec<usize>>,
+        set_of: Vec<u32>,
+    ) -> DFA {
+        let stride = classes.count();
+        assert!(
+            rows.len() * stride <= u32::MAX as usize,
+            "DFA with {} states is too large to index",
+            rows.len()
+        );
 
-            for state in 0..num_states {
-                if let Some(&Some(target)) = self.states[state].get(c as usize) {
-                    if partition[group_idx].contains(target) {
-                        let g = state_to_group[state];
-                        involved
-                            .entry(g)
-                            .or_insert_with(|| BitSet::with_capacity(num_states))
-                            .insert(state);
+        let mut table = Vec::with_capacity(rows.len() * stride);
+        for row in rows {
+            debug_assert_eq!(row.len(), stride);
+            table.extend(row.iter().map(|&target| (target * stride) as u32));
+        }
+
+        let accepting = (0..rows.len()).filter(|&s| set_of[s] != 0).collect();
+        let mut dfa = DFA {
+            table,
+            stride,
+            classes,
+            start: (start * stride) as u32,
+            accepting,
+            pattern_sets,
+            set_of,
+            accel: vec![None; rows.len()],
+            kinds: Vec::new(),
+        };
+        dfa.kinds = dfa.classify();
+        dfa
+    }
+
+    /// Classifies every state by which states it can reach: those that
+    /// reach no accepting state are dead, and accepting ones that only
+    /// reach states accepting the same patterns are universal.
+    fn classify(&self) -> Vec<StateKind> {
+        let n = self.state_count();
+        let (sources, offsets) = self.predecessors();
+        let reaching = |roots: Vec<usize>| {
+            let mut seen = vec![false; n];
+            for &root in &roots {
+                seen[root] = true;
+            }
+            let mut stack = roots;
+            while let Some(state) = stack.pop() {
+                for &p in &sources[offsets[state]..offsets[state + 1]] {
+                    if !std::mem::replace(&mut seen[p], true) {
+                        stack.push(p);
                     }
                 }
             }
+            seen
+        };
 
-            for (g, ref states_involved) in involved {
-                let group = &partition[g];
-                if states_involved.len() < group.len() {
-                    let mut new_group = BitSet::with_capacity(num_states);
-                    for state in states_involved.iter() {
-                        new_group.insert(state);
-                    }
+        let live = reaching(self.accepting.iter().collect());
+        let changes = (0..n).filter(|&s| {
+            let set = self.set_of[s];
+            set == 0 || (0..self.stride).any(|c| self.set_of[self.next(s, c)] != set)
+        });
+        let can_change = reaching(changes.collect());
+        (0..n)
+            .map(|s| match (live[s], can_change[s]) {
+                (false, _) => StateKind::Dead,
+                (true, false) => StateKind::Universal,
+                (true, true) => StateKind::Normal,
+            })
+            .collect()
+    }
 
-                    let mut old_group = BitSet::with_capacity(num_states);
-                    for state in group.iter() {
-                        if !new_group.contains(state) {
-                            old_group.insert(state);
-                        }
-                    }
+    /// The states with a transition to `t` on any class, as
+    /// `sources[offsets[t]..offsets[t + 1]]`.
+    fn predecessors(&self) -> (Vec<usize>, Vec<usize>) {
+        let n = self.state_count();
+        let mut offsets = vec![0; n + 1];
+        for &target in &self.table {
+            offsets[target as usize / self.stride + 1] += 1;
+        }
+        for i in 1..offsets.len() {
+            offsets[i] += offsets[i - 1];
+        }
+
+        let mut fill = offsets.clone();
+        let mut sources = vec![0; self.table.len()];
+        for (i, &target) in self.table.iter().enumerate() {
+            let t = target as usize / self.stride;
+            sources[fill[t]] = i / self.stride;
+            fill[t] += 1;
+        }
+        (sources, offsets)
+    }
 
-                    partition[g] = new_group;
-                    partition.push(old_group);
-                    let new_group_idx = partition.len() - 1;
+    /// Finds the states that can be accelerated.
+    fn accelerate(&mut self) {
+        let mut members = vec![Vec::new(); self.stride];
+        for b in 0..=255u8 {
+            members[self.classes.get(b)].push(b);
+        }
 
-                    for state in partition[new_group_idx].iter() {
-                        state_to_group[state] = new_group_idx;
+        for state in 0..self.state_count() {
+            let mut escapes = Vec::new();
+            for (class, bytes) in members.iter().enumerate() {
+                if self.next(state, class) != state {
+                    escapes.extend_from_slice(bytes);
+                    if escapes.len() > 3 {
+                        break;
                     }
+                }
+            }
+            self.accel[state] = match *escapes.as_slice() {
+                [a] => Some(Accel::One(a)),
+                [a, b] => Some(Accel::Two(a, b)),
+                [a, b, c] => Some(Accel::Three(a, b, c)),
+                _ => None,
+            };
+        }
+    }
+
+    /// Whether matching skips ahead with memchr in `state`.
+    pub fn is_accelerated(&self, state: usize) -> bool {
+        self.accel[state].is_some()
+    }
+
+    /// Where matching continues after entering the state with row offset
+    /// `id` at position `at`.
+    fn enter(&self, id: usize, input: &[u8], at: usize) -> usize {
+        match &self.accel[id / self.stride] {
+            Some(accel) => accel.skip(input, at),
+            None => at,
+        }
+    }
+
+    fn enter_rev(&self, id: usize, input: &[u8], end: usize) -> usize {
+        match &self.accel[id / self.stride] {
+            Some(accel) => accel.skip_rev(input, end),
+            None => end,
+        }
+    }
+
+    /// IDs of the patterns that accept in `state`, in increasing order.
+    pub fn patterns(&self, state: usize) -> &[usize] {
+        &self.pattern_sets[self.set_of[state] as usize]
+    }
+
+    pub fn kind(&self, state: usize) -> StateKind {
+        self.kinds[state]
+    }
+
+    /// Number of states, including the dead state.
+    pub fn state_count(&self) -> usize {
+        self.table.len() / self.stride
+    }
+
+    pub fn start(&self) -> usize {
+        self.start as usize / self.stride
+    }
+
+    /// Index of the state reached from `state` on any byte in `class`.
+    pub fn next(&self, state: usize, class: usize) -> usize {
+        self.table[state * self.stride + class] as usize / self.stride
+    }
+
+    /// For every class and target, the states that step to that target on
+    /// that class. `sources[offsets[k]..offsets[k + 1]]` lists them, with
+    /// `k = class * state_count + target`.
+    fn inverse(&self) -> (Vec<usize>, Vec<usize>) {
+        let n = self.state_count();
+        let key = |class: usize, target: usize| class * n + target;
+
+        let mut offsets = vec![0; n * self.stride + 1];
+        for state in 0..n {
+            for class in 0..self.stride {
+                offsets[key(class, self.next(state, class)) + 1] += 1;
+            }
+        }
+        for i in 1..offsets.len() {
+            offsets[i] += offsets[i - 1];
+        }
+
+        let mut fill = offsets.clone();
+        let mut sources = vec![0; n * self.stride];
+        for state in 0..n {
+            for class in 0..self.stride {
+                let k = key(class, self.next(state, class));
+                sources[fill[k]] = state;
+                fill[k] += 1;
+            }
+        }
+        (sources, offsets)
+    }
+
+    /// Hopcroft's algorithm: starts from the states grouped by the
+    /// patterns they accept and splits blocks until no block has states
+    /// that disagree on which block a class leads to. Returns the block of
+    /// every state.
+    fn refine(&self) -> Vec<usize> {
+        let n = self.state_count();
+        let k = self.stride;
+        let (sources, offsets) = self.inverse();
+
+        let group_of: Vec<usize> = self.set_of.iter().map(|&set| set as usize).collect();
+        let mut partition = Partition::new(&group_of, self.pattern_sets.len());
+
+        // Every block but one has to be a splitter, so leave out the largest.
+        let mut worklist = Vec::new();
+        let largest = (0..partition.len()).max_by_key(|&b| partition.size(b));
+        for block in (0..partition.len()).filter(|&b| Some(b) != largest) {
+            for class in 0..k {
+                worklist.push((block, class));
+            }
+        }
+
+        let mut splitter = Vec::new();
+        let mut touched = Vec::new();
+        while let Some((block, class)) = worklist.pop() {
+            splitter.clear();
+            splitter.extend_from_slice(partition.members(block));
+            for &target in &splitter {
+                let key = class * n + target;
+                for &source in &sources[offsets[key]..offsets[key + 1]] {
+                    partition.mark(source, &mut touched);
+                }
+            }
 
-                    for &a in &alphabet {
-                        worklist.push_back((g, a));
-                        worklist.push_back((new_group_idx, a));
+            for b in touched.drain(..) {
+                if let Some(new) = partition.split(b) {
+                    // If `b` is still queued it now holds only the other
+                    // half, and otherwise the smaller half is enough to
+                    // split on; `split` makes `new` the smaller either way.
+                    for c in 0..k {
+                        worklist.push((new, c));
                     }
                 }
             }
         }
+
+        partition.block_of
     }
 
     pub fn minimize(&self) -> DFA {
-        let num_states = self.states.len();
+        let block_of = self.refine();
+        let num_blocks = block_of.iter().max().map_or(0, |&b| b + 1);
 
-        let mut partition: Vec<BitSet> = Vec::new();
-        let mut state_to_group = vec![0; num_states];
+        // Keep the dead state's block at `DEAD`.
+        let dead_block = block_of[DEAD];
+        let renumber = |b: usize| match b {
+            b if b == dead_block => DEAD,
+            b if b < dead_block => b + 1,
+            b => b,
+        };
 
-        // Step 1: Initialize partition with accepting and non-accepting states
-        let accepting = self.accepting.clone();
-        let mut non_accepting = BitSet::with_capacity(num_states);
-        for state in 0..num_states {
-            if !accepting.contains(state) {
-                non_accepting.insert(state);
+        let mut new_rows = vec![vec![DEAD; self.stride]; num_blocks];
+        let mut new_set_of = vec![0; num_blocks];
+        let mut seen = vec![false; num_blocks];
+
+        for (state, &block) in block_of.iter().enumerate() {
+            if std::mem::replace(&mut seen[block], true) {
+                continue;
+            }
+            let row = &mut new_rows[renumber(block)];
+            for (class, target) in row.iter_mut().enumerate() {
+                *target = renumber(block_of[self.next(state, class)]);
             }
+            new_set_of[renumber(block)] = self.set_of[state];
         }
 
-        if !accepting.is_empty() {
-            partition.push(accepting);
+        let mut dfa = DFA::with_sets(
+            &new_rows,
+            self.classes.clone(),
+            renumber(block_of[self.start()]),
+            self.pattern_sets.clone(),
+            new_set_of,
+        );
+        dfa.accelerate();
+        dfa
+    }
+
+    pub fn matches(&self, input: &str) -> bool {
+        self.accepting
+            .contains(self.feed(self.start(), input.as_bytes()))
+    }
+
+    /// IDs of the patterns that match the whole of `input`.
+    pub fn matching_patterns(&self, input: &[u8]) -> &[usize] {
+        self.patterns(self.feed(self.start(), input))
+    }
+
+    /// Index of the state `input` leads to from `state`, so that input can
+    /// be read in pieces. Stops early in a dead or universal state, since
+    /// the rest of the input cannot change which patterns accept.
+    pub fn feed(&self, state: usize, input: &[u8]) -> usize {
+        let mut state = state * self.stride;
+        let mut i = self.enter(state, input, 0);
+
+        loop {
+            if self.kinds[state / self.stride] != StateKind::Normal {
+                return state / self.stride;
+            }
+            loop {
+                if i == input.len() {
+                    return state / self.stride;
+                }
+                let next = self.table[state + self.classes.get(input[i])] as usize;
+                i += 1;
+                if next != state {
+                    state = next;
+                    i = self.enter(state, input, i);
+                    break;
+                }
+            }
         }
-        if !non_accepting.is_empty() {
-            partition.push(non_accepting);
+    }
+
+    /// Length of the longest prefix of `input` that matches.
+    pub fn longest_match(&self, input: &[u8]) -> Option<usize> {
+        let mut state = self.start as usize;
+        let mut i = self.enter(state, input, 0);
+        let mut last = None;
+
+        loop {
+            let id = state / self.stride;
+            match self.kinds[id] {
+                StateKind::Dead => return last,
+                StateKind::Universal => return Some(input.len()),
+                StateKind::Normal => {}
+            }
+            let accepting = self.accepting.contains(id);
+            if accepting {
+                last = Some(i);
+            }
+            loop {
+                if i == input.len() {
+                    return last;
+                }
+                let next = self.table[state + self.classes.get(input[i])] as usize;
+                i += 1;
+                if next != state {
+                    state = next;
+                    i = self.enter(state, input, i);
+                    break;
+                }
+                if accepting {
+                    last = Some(i);
+                }
+            }
         }
+    }
 
-        for (i, group) in partition.iter().enumerate() {
-            for state in group.iter() {
-                state_to_group[state] = i;
+    /// The pattern `priority` picks among those matching a prefix of
+    /// `input`, and the length of its longest such match.
+    pub fn longest_match_with(&self, input: &[u8], priority: Priority) -> Option<(usize, usize)> {
+        let mut state = self.start as usize;
+        let mut i = self.enter(state, input, 0);
+        let mut best = None;
+
+        loop {
+            let id = state / self.stride;
+            match self.kinds[id] {
+                StateKind::Dead => return best,
+                StateKind::Universal => {
+                    return priority.prefer(best, self.patterns(id), input.len())
+                }
+                StateKind::Normal => {}
+            }
+            let accepting = self.accepting.contains(id);
+            if accepting {
+                best = priority.prefer(best, self.patterns(id), i);
+            }
+            loop {
+                if i == input.len() {
+                    return best;
+                }
+                let next = self.table[state + self.classes.get(input[i])] as usize;
+                i += 1;
+                if next != state {
+                    state = next;
+                    i = self.enter(state, input, i);
+                    break;
+                }
+                if accepting {
+                    best = priority.prefer(best, self.patterns(id), i);
+                }
             }
         }
+    }
 
-        // Step 2: Refinement loop
-        self.refine(&mut partition, &mut state_to_group, num_states);
+    /// Length of the shortest prefix of `input` that matches, found by
+    /// stopping at the first accepting state.
+    pub fn shortest_match(&self, input: &[u8]) -> Option<usize> {
+        let mut state = self.start as usize;
+        let mut i = 0;
 
-        // Step 3: Build new DFA
-        let mut new_states = vec![[None; 256]; partition.len()];
-        let mut new_accepting = BitSet::with_capacity(partition.len());
-        let new_start = state_to_group[self.start];
+        loop {
+            let id = state / self.stride;
+            if self.accepting.contains(id) {
+                return Some(i);
+            }
+            if self.kinds[id] == StateKind::Dead {
+                return None;
+            }
+            i = self.enter(state, input, i);
+            loop {
+                if i == input.len() {
+                    return None;
+                }
+                let next = self.table[state + self.classes.get(input[i])] as usize;
+                i += 1;
+                if next != state {
+                    state = next;
+                    break;
+                }
+            }
+        }
+    }
 
-        for (i, group) in partition.iter().enumerate() {
-            let rep = group.iter().next().unwrap();
-            for (b, &target) in self.states[rep].iter().enumerate() {
-                if let Some(t) = target {
-                    new_states[i][b] = Some(state_to_group[t]);
+    /// Reads `input` from the end backwards and returns the length of the
+    /// longest suffix that leads to an accepting state. On the DFA of a
+    /// reversed regex, that is the longest suffix the original matches.
+    pub fn longest_match_rev(&self, input: &[u8]) -> Option<usize> {
+        let mut state = self.start as usize;
+        let mut end = self.enter_rev(state, input, input.len());
+        let mut last = None;
+
+        loop {
+            let id = state / self.stride;
+            match self.kinds[id] {
+                StateKind::Dead => return last,
+                StateKind::Universal => return Some(input.len()),
+                StateKind::Normal => {}
+            }
+            let accepting = self.accepting.contains(id);
+            if accepting {
+                last = Some(input.len() - end);
+            }
+            loop {
+                if end == 0 {
+                    return last;
+                }
+                let next = self.table[state + self.classes.get(input[end - 1])] as usize;
+                end -= 1;
+                if next != state {
+                    state = next;
+                    end = self.enter_rev(state, input, end);
+                    break;
+                }
+                if accepting {
+                    last = Some(input.len() - end);
                 }
             }
+        }
+    }
+}
+
+/// A partition of states into blocks, each stored as a contiguous run of
+/// `elements`. Marking a state moves it to the front of its block, so a
+/// split only touches the states that were marked.
+struct Partition {
+    elements: Vec<usize>,
+    location: Vec<usize>,
+    block_of: Vec<usize>,
+    /// Start, end and number of marked states for each block.
+    blocks: Vec<(usize, usize, usize)>,
+}
 
-            if self.accepting.contains(rep) {
-                new_accepting.insert(i);
+impl Partition {
+    /// One block for each group in `0..groups` that has any states, where
+    /// `group_of` gives the group of every state.
+    fn new(group_of: &[usize], groups: usize) -> Self {
+        let n = group_of.len();
+        let mut elements: Vec<usize> = (0..n).collect();
+        elements.sort_by_key(|&s| group_of[s]);
+
+        let mut blocks = Vec::with_capacity(groups);
+        let mut block_of = vec![0; n];
+        let mut start = 0;
+        while start < n {
+            let group = group_of[elements[start]];
+            let mut end = start;
+            while end < n && group_of[elements[end]] == group {
+                block_of[elements[end]] = blocks.len();
+                end += 1;
             }
+            blocks.push((start, end, 0));
+            start = end;
+        }
+
+        let mut location = vec![0; n];
+        for (i, &s) in elements.iter().enumerate() {
+            location[s] = i;
         }
 
-        DFA {
-            states: new_states,
-            start: new_start,
-            accepting: new_accepting,
+        Self {
+            elements,
+            location,
+            block_of,
+            blocks,
         }
     }
 
-    pub fn matches(&self, input: &str) -> bool {
-        let mut state = self.start;
+    fn len(&self) -> usize {
+        self.blocks.len()
+    }
 
-        for &b in input.as_bytes() {
-            match self.states[state][b as usize] {
-                Some(next) => state = next,
-                None => return false,
-            }
+    fn size(&self, block: usize) -> usize {
+        let (start, end, _) = self.blocks[block];
+        end - start
+    }
+
+    fn members(&self, block: usize) -> &[usize] {
+        let (start, end, _) = self.blocks[block];
+        &self.elements[start..end]
+    }
+
+    /// Marks `state`, recording its block in `touched` on the block's
+    /// first mark.
+    fn mark(&mut self, state: usize, touched: &mut Vec<usize>) {
+        let block = self.block_of[state];
+        let (start, _, marked) = self.blocks[block];
+        let at = self.location[state];
+        if at < start + marked {
+            return;
+        }
+        if marked == 0 {
+            touched.push(block);
+        }
+
+        let swap = start + marked;
+        let other = self.elements[swap];
+        self.elements.swap(at, swap);
+        self.location[other] = at;
+        self.location[state] = swap;
+        self.blocks[block].2 += 1;
+    }
+
+    /// Splits the marked states of `block` from the unmarked ones and
+    /// clears the marks. The smaller half becomes a new block, whose index
+    /// is returned; nothing happens if every state was marked.
+    fn split(&mut self, block: usize) -> Option<usize> {
+        let (start, end, marked) = self.blocks[block];
+        self.blocks[block].2 = 0;
+        if marked == end - start {
+            return None;
         }
 
-        self.accepting.contains(state)
+        let mid = start + marked;
+        let new = self.blocks.len();
+        if marked <= end - mid {
+            self.blocks[block] = (mid, end, 0);
+            self.blocks.push((start, mid, 0));
+        } else {
+            self.blocks[block] = (start, mid, 0);
+            self.blocks.push((mid, end, 0));
+        }
+        let (new_start, new_end, _) = self.blocks[new];
+        for &s in &self.elements[new_start..new_end] {
+            self.block_of[s] = new;
+        }
+        Some(new)
     }
 }
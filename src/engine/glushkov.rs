@@ -0,0 +1,238 @@
+use crate::{
+    ast::Regex,
+    engine::nfa::{chain, Edge, State, Transition, NFA},
+};
+
+/// The position automaton of a regex: one position per byte, class or dot
+/// in the AST, with the positions that may start and end a match and the
+/// positions that may follow each one.
+#[derive(Debug)]
+pub(crate) struct Glushkov {
+    /// The byte ranges each position matches.
+    pub positions: Vec<Vec<(u8, u8)>>,
+    pub first: Vec<usize>,
+    pub last: Vec<usize>,
+    pub follow: Vec<Vec<usize>>,
+    /// Whether the empty string matches.
+    pub nullable: bool,
+}
+
+/// First and last positions of a sub-expression, and whether it is nullable.
+struct Summary {
+    first: Vec<usize>,
+    last: Vec<usize>,
+    nullable: bool,
+}
+
+impl Glushkov {
+    pub fn new(regex: &Regex) -> Self {
+        let mut glushkov = Glushkov {
+            positions: Vec::new(),
+            first: Vec::new(),
+            last: Vec::new(),
+            follow: Vec::new(),
+            nullable: false,
+        };
+        let summary = glushkov.visit(regex);
+        for follow in &mut glushkov.follow {
+            follow.sort_unstable();
+            follow.dedup();
+        }
+        glushkov.first = summary.first;
+        glushkov.last = summary.last;
+        glushkov.nullable = summary.nullable;
+        glushkov
+    }
+
+    fn position(&mut self, ranges: Vec<(u8, u8)>) -> Summary {
+        let p = self.positions.len();
+        self.positions.push(ranges);
+        self.follow.push(Vec::new());
+        Summary {
+            first: vec![p],
+            last: vec![p],
+            nullable: false,
+        }
+    }
+
+    fn link(&mut self, from: &[usize], to: &[usize]) {
+        for &p in from {
+            self.follow[p].extend_from_slice(to);
+        }
+    }
+
+    fn visit(&mut self, regex: &Regex) -> Summary {
+        match regex {
+            Regex::Empty => Summary {
+                first: vec![],
+                last: vec![],
+                nullable: true,
+            },
+            Regex::Byte(b) => self.position(vec![(*b, *b)]),
+            Regex::Class(ranges) => self.position(ranges.clone()),
+            Regex::Dot => self.position(vec![(0, 255)]),
+
+            Regex::Concat(_, _) => {
+                let mut parts = chain(regex).into_iter();
+                let mut acc = self.visit(parts.next().unwrap());
+                for part in parts {
+                    let next = self.visit(part);
+                    self.link(&acc.last, &next.first);
+                    if acc.nullable {
+                        acc.first.extend(next.first);
+                    }
+                    acc.last = if next.nullable {
+                        let mut last = acc.last;
+                        last.extend(next.last);
+                        last
+                    } else {
+                        next.last
+                    };
+                    acc.nullable &= next.nullable;
+                }
+                acc
+            }
+
+            Regex::Alt(_, _) => {
+                let mut acc = Summary {
+                    first: vec![],
+                    last: vec![],
+                    nullable: false,
+                };
+                for part in chain(regex) {
+                    let next = self.visit(part);
+                    acc.first.extend(next.first);
+                    acc.last.extend(next.last);
+                    acc.nullable |= next.nullable;
+                }
+                acc
+            }
+
+            Regex::Star(inner) | Regex::Plus(inner) => {
+                let mut summary = self.visit(inner);
+                self.link(&summary.last, &summary.first);
+                summary.nullable |= matches!(regex, Regex::Star(_));
+                summary
+            }
+
+            Regex::Optional(inner) => {
+                let mut summary = self.visit(inner);
+                summary.nullable = true;
+                summary
+            }
+        }
+    }
+
+    /// A bound on the states of the DFA, without building it. Each DFA
+    /// state past the initial one is the set of positions the input so far
+    /// can end in, so there are at most 2^m of them for m positions, plus
+    /// the initial and dead states. If no two positions that may come next
+    /// share a byte, every such set has a single position.
+    pub fn dfa_states_bound(&self) -> usize {
+        let deterministic = std::iter::once(&self.first)
+            .chain(&self.follow)
+            .all(|next| {
+                let mut seen = [false; 256];
+                next.iter()
+                    .flat_map(|&p| &self.positions[p])
+                    .flat_map(|&(lo, hi)| lo..=hi)
+                    .all(|b| !std::mem::replace(&mut seen[b as usize], true))
+            });
+        let sets = if deterministic {
+            self.positions.len()
+        } else {
+            1usize
+                .checked_shl(self.positions.len() as u32)
+                .unwrap_or(usize::MAX)
+        };
+        sets.saturating_add(2)
+    }
+
+    /// An epsilon-free NFA with state 0 as the initial state and state
+    /// `p + 1` for each position `p`. Every edge into a position carries
+    /// that position's ranges, and the last positions accept, as does the
+    /// initial state when the expression is nullable.
+    pub fn to_nfa(&self) -> NFA {
+        let mut states = vec![State { edges: vec![] }; self.positions.len() + 1];
+
+        let mut enter = |from: usize, to: usize| {
+            for &(lo, hi) in &self.positions[to] {
+                let label = if lo == hi {
+                    Transition::Byte(lo)
+                } else {
+                    Transition::Range(lo, hi)
+                };
+                states[from].edges.push(Edge { label, to: to + 1 });
+            }
+        };
+
+        for &q in &self.first {
+            enter(0, q);
+        }
+        for (p, follow) in self.follow.iter().enumerate() {
+            for &q in follow {
+                enter(p + 1, q);
+            }
+        }
+
+        let mut accepts: Vec<usize> = self.last.iter().map(|&p| p + 1).collect();
+        if self.nullable {
+            accepts.insert(0, 0);
+        }
+
+        NFA {
+            states,
+            start: 0,
+            accepts: vec![accepts],
+        }
+    }
+}
+
+#[cfg(test)]
+mod glushkov_tests {
+    use super::*;
+    use crate::parser::parser::parse;
+
+    #[test]
+    fn one_state_per_position() {
+        let glushkov = Glushkov::new(&parse("(a|b)*a.."));
+        assert_eq!(glushkov.positions.len(), 5);
+        assert_eq!(glushkov.to_nfa().states.len(), 6);
+    }
+
+    #[test]
+    fn dfa_states_bound() {
+        // one state per position, the initial state and the dead state
+        let glushkov = Glushkov::new(&parse("(a|b)+c(a|b)+"));
+        assert_eq!(glushkov.dfa_states_bound(), 7);
+        assert!(glushkov.to_nfa().to_dfa().state_count() <= 7);
+
+        let glushkov = Glushkov::new(&parse("(a|b)*a(a|b)"));
+        assert_eq!(glushkov.dfa_states_bound(), (1 << 5) + 2);
+    }
+
+    #[test]
+    fn first_last_and_follow() {
+        // positions: a=0, b=1, c=2
+        let glushkov = Glushkov::new(&parse("(ab?)*c"));
+        assert_eq!(glushkov.first, vec![0, 2]);
+        assert_eq!(glushkov.last, vec![2]);
+        assert_eq!(glushkov.follow[0], vec![0, 1, 2]);
+        assert_eq!(glushkov.follow[1], vec![0, 2]);
+        assert!(glushkov.follow[2].is_empty());
+        assert!(!glushkov.nullable);
+    }
+
+    #[test]
+    fn accepts_without_epsilon_edges() {
+        // positions: a=0, b=1, c=2
+        let nfa = Glushkov::new(&parse("(a|b*)?c*")).to_nfa();
+        for state in &nfa.states {
+            assert!(state
+                .edges
+                .iter()
+                .all(|edge| edge.label != Transition::Epsilon));
+        }
+        assert_eq!(nfa.accepts, vec![vec![0, 1, 2, 3]]);
+    }
+}
@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use bit_set::BitSet;
+
+use crate::{
+    ast::Regex,
+    engine::{
+        classes::ByteClasses,
+        dfa::DFA,
+        nfa::{from_regex, from_regexes, NFA},
+    },
+};
+
+const UNKNOWN: u32 = u32::MAX;
+const DEAD: u32 = u32::MAX - 1;
+
+/// How many times the cache may be cleared during one match before we
+/// start checking whether it is earning its keep.
+const MIN_CLEARS: usize = 3;
+
+/// Below this many input bytes per newly built state, the cache is
+/// thrashing and plain NFA simulation is faster.
+const MIN_BYTES_PER_STATE: usize = 10;
+
+/// A DFA that is determinized on demand while matching. States are built
+/// the first time the input reaches them and kept in a bounded cache, which
+/// is cleared when full. Compilation is therefore linear in the pattern,
+/// and matching never builds more states than the input visits.
+#[derive(Debug)]
+pub struct LazyDFA {
+    nfa: NFA,
+    classes: ByteClasses,
+    capacity: usize,
+    cache: Mutex<Cache>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Cache {
+    sets: Vec<BitSet>,
+    ids: HashMap<BitSet, u32>,
+    /// One row of `stride` entries per state, one entry per byte class.
+    transitions: Vec<u32>,
+    stride: usize,
+    accepting: Vec<bool>,
+}
+
+impl Cache {
+    fn new(stride: usize) -> Self {
+        Self {
+            sets: Vec::new(),
+            ids: HashMap::new(),
+            transitions: Vec::new(),
+            stride,
+            accepting: Vec::new(),
+        }
+    }
+
+    /// Whether `state`, as returned by `LazyDFA::feed`, accepts.
+    pub(crate) fn is_accepting(&self, state: u32) -> bool {
+        state != DEAD && self.accepting[state as usize]
+    }
+
+    /// Whether `state` is the empty set of NFA states, which no input
+    /// leaves.
+    pub(crate) fn is_dead(&self, state: u32) -> bool {
+        state == DEAD
+    }
+
+    fn clear(&mut self) {
+        self.sets.clear();
+        self.ids.clear();
+        self.transitions.clear();
+        self.accepting.clear();
+    }
+
+    fn add(&mut self, set: BitSet, accepting: bool) -> u32 {
+        let id = self.sets.len() as u32;
+        self.accepting.push(accepting);
+        self.transitions
+            .extend(std::iter::repeat_n(UNKNOWN, self.stride));
+        self.ids.insert(set.clone(), id);
+        self.sets.push(set);
+        id
+    }
+}
+
+impl LazyDFA {
+    /// Creates a lazy DFA that caches at most `capacity` states.
+    pub(crate) fn new(nfa: NFA, capacity: usize) -> Self {
+        let classes = nfa.byte_classes();
+        let cache = Cache::new(classes.count());
+        Self {
+            nfa,
+            classes,
+            capacity: capacity.clamp(1, DEAD as usize),
+            cache: Mutex::new(cache),
+        }
+    }
+
+    /// An empty cache of states for `start` and `feed`, apart from the one
+    /// matching uses.
+    pub(crate) fn cache(&self) -> Cache {
+        Cache::new(self.classes.count())
+    }
+
+    /// The ID in `cache` of the state the DFA starts in.
+    pub(crate) fn start(&self, cache: &mut Cache) -> u32 {
+        let start = self.nfa.start_set();
+        match cache.ids.get(&start) {
+            Some(&id) => id,
+            None => {
+                if cache.sets.len() >= self.capacity {
+                    cache.clear();
+                }
+                let accepting = self.nfa.is_accepting(&start);
+                cache.add(start, accepting)
+            }
+        }
+    }
+
+    /// The ID in `cache` of the state `input` leads to from `state`, so that
+    /// input can be read in pieces. A full cache is cleared to make room,
+    /// which leaves only the state returned valid.
+    pub(crate) fn feed(&self, cache: &mut Cache, mut state: u32, input: &[u8]) -> u32 {
+        for &b in input {
+            if state == DEAD {
+                break;
+            }
+            let slot = state as usize * cache.stride + self.classes.get(b);
+            let mut next = cache.transitions[slot];
+            if next == UNKNOWN {
+                let set = self
+                    .nfa
+                    .epsilon_closure(&self.nfa.move_on(&cache.sets[state as usize], b));
+                let mut cleared = false;
+                next = if set.is_empty() {
+                    DEAD
+                } else if let Some(&id) = cache.ids.get(&set) {
+                    id
+                } else {
+                    if cache.sets.len() >= self.capacity {
+                        cache.clear();
+                        cleared = true;
+                    }
+                    let accepting = self.nfa.is_accepting(&set);
+                    cache.add(set, accepting)
+                };
+                if !cleared {
+                    cache.transitions[slot] = next;
+                }
+            }
+            state = next;
+        }
+        state
+    }
+
+    pub fn matches(&self, input: &str) -> bool {
+        self.longest_match(input.as_bytes()) == Some(input.len())
+    }
+
+    /// Length of the longest prefix of `bytes` that matches.
+    pub fn longest_match(&self, bytes: &[u8]) -> Option<usize> {
+        self.run(bytes, false)
+    }
+
+    /// Length of the shortest prefix of `bytes` that matches, found by
+    /// stopping at the first accepting state.
+    pub fn shortest_match(&self, bytes: &[u8]) -> Option<usize> {
+        self.run(bytes, true)
+    }
+
+    /// The longest match, or with `earliest` the shortest.
+    fn run(&self, bytes: &[u8], earliest: bool) -> Option<usize> {
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        let mut state = self.start(&mut cache);
+
+        let mut last = cache.accepting[state as usize].then_some(0);
+        if earliest && last.is_some() {
+            return last;
+        }
+        let mut clears = 0;
+        let mut bytes_since_clear = 0;
+        let mut built_since_clear = 0;
+
+        for (i, &b) in bytes.iter().enumerate() {
+            let slot = state as usize * cache.stride + self.classes.get(b);
+            let mut next = cache.transitions[slot];
+
+            if next == UNKNOWN {
+                let set = self
+                    .nfa
+                    .epsilon_closure(&self.nfa.move_on(&cache.sets[state as usize], b));
+
+                let mut cleared = false;
+                next = if set.is_empty() {
+                    DEAD
+                } else if let Some(&id) = cache.ids.get(&set) {
+                    id
+                } else {
+                    if cache.sets.len() >= self.capacity {
+                        clears += 1;
+                        if clears >= MIN_CLEARS
+                            && bytes_since_clear < MIN_BYTES_PER_STATE * built_since_clear
+                        {
+                            let rest = self.nfa.simulate(set, &bytes[i + 1..], earliest);
+                            return rest.map(|n| i + 1 + n).or(last);
+                        }
+                        cache.clear();
+                        cleared = true;
+                        bytes_since_clear = 0;
+                        built_since_clear = 0;
+                    }
+                    built_since_clear += 1;
+                    let accepting = self.nfa.is_accepting(&set);
+                    cache.add(set, accepting)
+                };
+
+                // A clear forgets the state we came from, so there is no
+                // row left to record the transition in.
+                if !cleared {
+                    cache.transitions[slot] = next;
+                }
+            }
+
+            if next == DEAD {
+                break;
+            }
+            state = next;
+            bytes_since_clear += 1;
+            if cache.accepting[state as usize] {
+                last = Some(i + 1);
+                if earliest {
+                    break;
+                }
+            }
+        }
+
+        last
+    }
+}
+
+/// Which DFA a `Deferred` builds from its patterns.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Direction {
+    /// The leftmost-longest search; see `NFA::try_to_leftmost_dfa`.
+    Leftmost,
+    /// The patterns with any prefix allowed.
+    Unanchored,
+    /// The patterns reversed, for `DFA::longest_match_rev`.
+    Reverse,
+}
+
+/// A DFA that is built in full, NFA and all, the first time it is asked
+/// for, for searches that not every user of a pattern needs.
+#[derive(Debug)]
+pub(crate) struct Deferred {
+    patterns: Arc<[Regex]>,
+    direction: Direction,
+    max_states: usize,
+    dfa: OnceLock<Option<DFA>>,
+}
+
+impl Deferred {
+    pub(crate) fn new(patterns: Arc<[Regex]>, direction: Direction, max_states: usize) -> Self {
+        Self {
+            patterns,
+            direction,
+            max_states,
+            dfa: OnceLock::new(),
+        }
+    }
+
+    /// The minimized DFA, or `None` if it needs more than `max_states`.
+    pub(crate) fn dfa(&self) -> Option<&DFA> {
+        self.dfa
+            .get_or_init(|| {
+                let nfa = match &self.patterns[..] {
+                    [pattern] => from_regex(pattern),
+                    patterns => from_regexes(patterns),
+                };
+                let dfa = match self.direction {
+                    Direction::Leftmost => nfa.try_to_leftmost_dfa(self.max_states),
+                    Direction::Unanchored => nfa.unanchored().try_to_dfa(self.max_states),
+                    Direction::Reverse => nfa.reverse().try_to_dfa(self.max_states),
+                };
+                Some(dfa.ok()?.minimize())
+            })
+            .as_ref()
+    }
+}
@@ -1,3 +1,10 @@
-mod nfa;
+pub mod nfa;
+pub(crate) mod glushkov;
+pub mod bitparallel;
+pub mod classes;
 pub mod dfa;
 pub mod compiler;
+pub mod derivative;
+pub mod lazy;
+pub mod prefilter;
+pub(crate) mod pike;
@@ -1,15 +1,34 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 use bit_set::BitSet;
 
-use crate::{ast::Regex, engine::dfa::DFA};
+use crate::{
+    ast::Regex,
+    engine::{
+        classes::ByteClasses,
+        dfa::{DEAD, DFA},
+    },
+    error::Error,
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Transition {
     Byte(u8),
+    /// Any byte from `lo` to `hi` inclusive.
+    Range(u8, u8),
     Epsilon,
 }
 
+impl Transition {
+    pub(crate) fn accepts(&self, byte: u8) -> bool {
+        match *self {
+            Transition::Byte(b) => b == byte,
+            Transition::Range(lo, hi) => lo <= byte && byte <= hi,
+            Transition::Epsilon => false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Edge {
     pub label: Transition,
@@ -21,32 +40,39 @@ pub struct State {
     pub edges: Vec<Edge>,
 }
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, PartialEq)]
 pub struct NFA {
     pub states: Vec<State>,
     pub start: usize,
-    pub accept: usize,
+    /// The accepting states of each pattern, indexed by pattern ID. Thompson
+    /// construction gives each pattern one; Glushkov's accepts in every last
+    /// position.
+    pub accepts: Vec<Vec<usize>>,
 }
 
 impl NFA {
-    fn new_state(&mut self) -> usize {
-        self.states.push(State { edges: vec![] });
-        self.states.len() - 1
+    pub(crate) fn is_accepting(&self, states: &BitSet) -> bool {
+        self.accepts
+            .iter()
+            .flatten()
+            .any(|&accept| states.contains(accept))
     }
 
-    fn add_transition(&mut self, from: usize, to: usize, label: Transition) {
-        self.states[from].edges.push(Edge { label, to })
+    /// IDs of the patterns with an accepting state in `states`, in order.
+    pub(crate) fn patterns(&self, states: &BitSet) -> Vec<usize> {
+        (0..self.accepts.len())
+            .filter(|&id| self.accepts_pattern(id, states))
+            .collect()
     }
 
-    fn offset(&mut self, offset: usize) {
-        self.states.iter_mut().for_each(|s| {
-            s.edges.iter_mut().for_each(|e| e.to += offset);
-        });
-        self.start += offset;
-        self.accept += offset;
+    fn accepts_pattern(&self, id: usize, states: &BitSet) -> bool {
+        self.accepts[id]
+            .iter()
+            .any(|&accept| states.contains(accept))
     }
 
-    fn epsilon_closure(&self, states: &BitSet) -> BitSet {
+    pub(crate) fn epsilon_closure(&self, states: &BitSet) -> BitSet {
         let mut closure = BitSet::with_capacity(self.states.len());
         let mut stack: Vec<usize> = Vec::new();
 
@@ -68,25 +94,169 @@ impl NFA {
         closure
     }
 
-    fn move_on(&self, states: &BitSet, byte: u8) -> BitSet {
+    pub(crate) fn move_on(&self, states: &BitSet, byte: u8) -> BitSet {
         let mut next = BitSet::with_capacity(self.states.len());
 
         for state in states {
             for edge in &self.states[state].edges {
-                if let Transition::Byte(b) = edge.label {
-                    if b == byte {
-                        next.insert(edge.to);
-                    }
+                if edge.label.accepts(byte) {
+                    next.insert(edge.to);
                 }
             }
         }
         next
     }
 
+    /// Groups bytes that no state tells apart: for every state and target,
+    /// the bytes leading there form one set, and classes never straddle a
+    /// set boundary.
+    pub(crate) fn byte_classes(&self) -> ByteClasses {
+        let mut sets: HashSet<[bool; 256]> = HashSet::new();
+        for state in &self.states {
+            let mut by_target: HashMap<usize, [bool; 256]> = HashMap::new();
+            for edge in &state.edges {
+                let (lo, hi) = match edge.label {
+                    Transition::Byte(b) => (b, b),
+                    Transition::Range(lo, hi) => (lo, hi),
+                    Transition::Epsilon => continue,
+                };
+                let set = by_target.entry(edge.to).or_insert([false; 256]);
+                set[lo as usize..=hi as usize].fill(true);
+            }
+            sets.extend(by_target.into_values());
+        }
+
+        let mut classes = ByteClasses::new();
+        for set in &sets {
+            classes.split(set);
+        }
+        classes
+    }
+
+    /// Runs the NFA over `input` starting from the closed set `current`,
+    /// tracking every live state at once. Returns the length of the
+    /// longest prefix of `input` after which the NFA accepts, or with
+    /// `earliest`, of the shortest, stopping there.
+    pub(crate) fn simulate(
+        &self,
+        mut current: BitSet,
+        input: &[u8],
+        earliest: bool,
+    ) -> Option<usize> {
+        let mut last = self.is_accepting(&current).then_some(0);
+        for (i, &b) in input.iter().enumerate() {
+            if current.is_empty() || earliest && last.is_some() {
+                break;
+            }
+            current = self.epsilon_closure(&self.move_on(&current, b));
+            if self.is_accepting(&current) {
+                last = Some(i + 1);
+            }
+        }
+        last
+    }
+
+    /// The closed set of states the NFA starts in.
+    pub(crate) fn start_set(&self) -> BitSet {
+        let mut start = BitSet::with_capacity(self.states.len());
+        start.insert(self.start);
+        self.epsilon_closure(&start)
+    }
+
+    /// The set of states reached from the closed set `current` by reading
+    /// `input`.
+    pub(crate) fn feed(&self, mut current: BitSet, input: &[u8]) -> BitSet {
+        for &b in input {
+            if current.is_empty() {
+                break;
+            }
+            current = self.epsilon_closure(&self.move_on(&current, b));
+        }
+        current
+    }
+
+    /// Matches by simulation, in O(n·m) time for n input bytes and m
+    /// states, without building any DFA states.
+    pub fn matches(&self, input: &str) -> bool {
+        self.longest_match(input.as_bytes()) == Some(input.len())
+    }
+
+    /// Length of the longest prefix of `input` that matches.
+    pub fn longest_match(&self, input: &[u8]) -> Option<usize> {
+        self.simulate(self.start_set(), input, false)
+    }
+
+    /// Length of the shortest prefix of `input` that matches, found by
+    /// stopping at the first accepting set.
+    pub fn shortest_match(&self, input: &[u8]) -> Option<usize> {
+        self.simulate(self.start_set(), input, true)
+    }
+
+    /// The NFA for the reversed language: every edge turned around, a new
+    /// start state with epsilon edges to the old accept states, and the old
+    /// start as the only accept state. The patterns of a multi-pattern NFA
+    /// are merged into one.
+    pub fn reverse(&self) -> NFA {
+        let mut states = vec![State { edges: vec![] }; self.states.len() + 1];
+        for (from, state) in self.states.iter().enumerate() {
+            for edge in &state.edges {
+                states[edge.to].edges.push(Edge {
+                    label: edge.label.clone(),
+                    to: from,
+                });
+            }
+        }
+
+        let start = self.states.len();
+        for &accept in self.accepts.iter().flatten() {
+            states[start].edges.push(Edge {
+                label: Transition::Epsilon,
+                to: accept,
+            });
+        }
+
+        NFA {
+            states,
+            start,
+            accepts: vec![vec![self.start]],
+        }
+    }
+
+    /// The NFA that also matches after any prefix: a new start state that
+    /// loops on every byte and has an epsilon edge to the old start.
+    pub fn unanchored(mut self) -> NFA {
+        let start = self.states.len();
+        self.states.push(State {
+            edges: vec![
+                Edge {
+                    label: Transition::Range(0, 255),
+                    to: start,
+                },
+                Edge {
+                    label: Transition::Epsilon,
+                    to: self.start,
+                },
+            ],
+        });
+        self.start = start;
+        self
+    }
+
     pub fn to_dfa(&self) -> DFA {
+        self.try_to_dfa(usize::MAX)
+            .expect("unbounded subset construction cannot exceed its limit")
+    }
+
+    /// Subset construction that gives up once more than `max_states` DFA
+    /// states would be needed.
+    pub fn try_to_dfa(&self, max_states: usize) -> Result<DFA, Error> {
+        let classes = self.byte_classes();
+        let representatives = classes.representatives();
+
         let mut state_map = HashMap::new();
-        let mut dfa_states = Vec::new();
-        let mut accepting = BitSet::with_capacity(self.states.len());
+        // Row `DEAD` stands for the empty set of NFA states.
+        let mut dfa_states = vec![vec![DEAD; classes.count()]];
+        let mut patterns = vec![Vec::new()];
 
         let mut queue = VecDeque::new();
 
@@ -94,182 +264,324 @@ impl NFA {
         start_set.insert(self.start);
         let start_closure = self.epsilon_closure(&start_set);
 
-        state_map.insert(start_closure.clone(), 0);
-        dfa_states.push([None; 256]);
+        let start = dfa_states.len();
+        state_map.insert(start_closure.clone(), start);
+        dfa_states.push(vec![DEAD; classes.count()]);
+        patterns.push(self.patterns(&start_closure));
 
-        queue.push_back(start_closure.clone());
+        queue.push_back(start_closure);
 
         while let Some(current_set) = queue.pop_front() {
             let current_idx = state_map[&current_set];
 
-            for byte in 0u8..=255 {
+            for (class, &byte) in representatives.iter().enumerate() {
                 let move_set = self.move_on(&current_set, byte);
                 if move_set.is_empty() {
                     continue;
                 }
                 let next_set = self.epsilon_closure(&move_set);
 
-                let next_idx = *state_map.entry(next_set.clone()).or_insert_with(|| {
-                    let idx = dfa_states.len();
-                    dfa_states.push([None; 256]);
-                    queue.push_back(next_set.clone());
-                    idx
-                });
+                let next_idx = match state_map.get(&next_set) {
+                    Some(&idx) => idx,
+                    None => {
+                        if dfa_states.len() >= max_states {
+                            return Err(Error::TooManyDfaStates { limit: max_states });
+                        }
+                        let idx = dfa_states.len();
+                        dfa_states.push(vec![DEAD; classes.count()]);
+                        patterns.push(self.patterns(&next_set));
+                        state_map.insert(next_set.clone(), idx);
+                        queue.push_back(next_set);
+                        idx
+                    }
+                };
 
-                dfa_states[current_idx][byte as usize] = Some(next_idx);
+                dfa_states[current_idx][class] = next_idx;
             }
+        }
+
+        Ok(DFA::from_rows(&dfa_states, classes, start, patterns))
+    }
 
-            if current_set.contains(self.accept) {
-                accepting.insert(current_idx);
+    /// Subset construction over the threads of a leftmost-longest search:
+    /// each DFA state is the list of NFA state sets of matches started at
+    /// successive positions, earliest first, with a state kept only in the
+    /// earliest set that reaches it. A new set starts at every position
+    /// until some set accepts, and sets started after the one that accepts
+    /// are dropped. The last position where the DFA accepts is then the end
+    /// of the leftmost-longest match, found in one pass however many
+    /// positions a match could start at.
+    pub fn try_to_leftmost_dfa(&self, max_states: usize) -> Result<DFA, Error> {
+        let classes = self.byte_classes();
+        let representatives = classes.representatives();
+        let start_set = self.start_set();
+
+        // The sets in order of start, and whether some set has accepted
+        let advance = |mut sets: Vec<BitSet>, mut matched: bool| {
+            let mut seen = BitSet::with_capacity(self.states.len());
+            if !matched {
+                sets.push(start_set.clone());
             }
-        }
+            let mut kept = Vec::with_capacity(sets.len());
+            for mut set in sets {
+                set.difference_with(&seen);
+                if set.is_empty() {
+                    continue;
+                }
+                seen.union_with(&set);
+                let accepting = self.is_accepting(&set);
+                kept.push(set);
+                if accepting {
+                    matched = true;
+                    break;
+                }
+            }
+            (kept, matched)
+        };
 
-        DFA {
-            states: dfa_states,
-            start: 0,
-            accepting,
+        let mut state_map = HashMap::new();
+        // Row `DEAD` stands for no sets left after a match.
+        let mut dfa_states = vec![vec![DEAD; classes.count()]];
+        let mut patterns = vec![Vec::new()];
+        let mut queue = VecDeque::new();
+
+        let key = advance(Vec::new(), false);
+        let start = dfa_states.len();
+        let union = |sets: &[BitSet]| {
+            sets.iter().fold(BitSet::new(), |mut all, set| {
+                all.union_with(set);
+                all
+            })
+        };
+        state_map.insert(key.clone(), start);
+        dfa_states.push(vec![DEAD; classes.count()]);
+        patterns.push(self.patterns(&union(&key.0)));
+        queue.push_back(key);
+
+        while let Some(current) = queue.pop_front() {
+            let current_idx = state_map[&current];
+            let (sets, matched) = &current;
+
+            for (class, &byte) in representatives.iter().enumerate() {
+                let moved = sets
+                    .iter()
+                    .map(|set| self.epsilon_closure(&self.move_on(set, byte)))
+                    .collect();
+                let next = advance(moved, *matched);
+                if next.0.is_empty() {
+                    continue;
+                }
+
+                let next_idx = match state_map.get(&next) {
+                    Some(&idx) => idx,
+                    None => {
+                        if dfa_states.len() >= max_states {
+                            return Err(Error::TooManyDfaStates { limit: max_states });
+                        }
+                        let idx = dfa_states.len();
+                        dfa_states.push(vec![DEAD; classes.count()]);
+                        patterns.push(self.patterns(&union(&next.0)));
+                        state_map.insert(next.clone(), idx);
+                        queue.push_back(next);
+                        idx
+                    }
+                };
+
+                dfa_states[current_idx][class] = next_idx;
+            }
         }
+
+        Ok(DFA::from_rows(&dfa_states, classes, start, patterns))
     }
 }
 
-pub fn from_regex(regex: &Regex) -> NFA {
-    match regex {
-        Regex::Byte(b) => {
-            let mut nfa = NFA {
-                states: vec![],
-                start: 0,
-                accept: 0,
-            };
-            let start = nfa.new_state();
-            let end = nfa.new_state();
-            nfa.start = start;
-            nfa.accept = end;
-            nfa.add_transition(start, end, Transition::Byte(*b));
-            nfa
-        }
+/// Target of an edge whose destination is not known yet.
+const DANGLING: usize = usize::MAX;
 
-        Regex::Dot => {
-            let mut nfa = NFA {
-                states: vec![],
-                start: 0,
-                accept: 0,
-            };
-            let start = nfa.new_state();
-            let end = nfa.new_state();
-            nfa.start = start;
-            nfa.accept = end;
-            for b in 0u8..=255 {
-                nfa.add_transition(start, end, Transition::Byte(b));
-            }
-            nfa
-        }
+/// A partly built automaton: its entry state, and the dangling edges that
+/// must be patched to whatever follows it.
+struct Fragment {
+    start: usize,
+    holes: Vec<(usize, usize)>,
+}
 
-        Regex::Concat(left, right) => {
-            let mut a = from_regex(left);
-            let mut b = from_regex(right);
+/// Thompson's construction over a single arena. Sub-expressions emit their
+/// states directly into `states` and leave their exits dangling, so nothing
+/// is ever copied or renumbered.
+struct Builder {
+    states: Vec<State>,
+}
 
-            let offset = a.states.len();
-            b.offset(offset);
+impl Builder {
+    fn new_state(&mut self) -> usize {
+        self.states.push(State { edges: vec![] });
+        self.states.len() - 1
+    }
 
-            a.add_transition(a.accept, b.start, Transition::Epsilon);
+    fn add_transition(&mut self, from: usize, to: usize, label: Transition) {
+        self.states[from].edges.push(Edge { label, to })
+    }
 
-            a.states.extend(b.states);
+    fn dangling(&mut self, from: usize, label: Transition) -> (usize, usize) {
+        self.add_transition(from, DANGLING, label);
+        (from, self.states[from].edges.len() - 1)
+    }
 
-            NFA {
-                states: a.states,
-                start: a.start,
-                accept: b.accept,
-            }
+    fn patch(&mut self, holes: Vec<(usize, usize)>, to: usize) {
+        for (state, edge) in holes {
+            self.states[state].edges[edge].to = to;
         }
+    }
 
-        Regex::Alt(left, right) => {
-            let mut a = from_regex(left);
-            let mut b = from_regex(right);
+    fn build(&mut self, regex: &Regex) -> Fragment {
+        match regex {
+            Regex::Empty => {
+                let start = self.new_state();
+                let hole = self.dangling(start, Transition::Epsilon);
+                Fragment {
+                    start,
+                    holes: vec![hole],
+                }
+            }
 
-            let mut nfa = NFA {
-                states: vec![],
-                start: 0,
-                accept: 0,
-            };
-            let start = nfa.new_state();
+            Regex::Byte(b) => {
+                let start = self.new_state();
+                let hole = self.dangling(start, Transition::Byte(*b));
+                Fragment {
+                    start,
+                    holes: vec![hole],
+                }
+            }
 
-            let offset_a = nfa.states.len();
-            a.offset(offset_a);
-            nfa.states.extend(a.states);
+            Regex::Dot => {
+                let start = self.new_state();
+                let hole = self.dangling(start, Transition::Range(0, 255));
+                Fragment {
+                    start,
+                    holes: vec![hole],
+                }
+            }
 
-            let offset_b = nfa.states.len();
-            b.offset(offset_b);
-            nfa.states.extend(b.states);
+            Regex::Class(ranges) => {
+                let start = self.new_state();
+                let holes = ranges
+                    .iter()
+                    .map(|&(lo, hi)| self.dangling(start, Transition::Range(lo, hi)))
+                    .collect();
+                Fragment { start, holes }
+            }
 
-            let accept = nfa.new_state();
+            Regex::Concat(_, _) => {
+                let mut parts = chain(regex).into_iter();
+                let first = self.build(parts.next().unwrap());
+                let mut holes = first.holes;
+                for part in parts {
+                    let next = self.build(part);
+                    self.patch(holes, next.start);
+                    holes = next.holes;
+                }
+                Fragment {
+                    start: first.start,
+                    holes,
+                }
+            }
 
-            nfa.add_transition(start, a.start, Transition::Epsilon);
-            nfa.add_transition(start, b.start, Transition::Epsilon);
-            nfa.add_transition(a.accept, accept, Transition::Epsilon);
-            nfa.add_transition(b.accept, accept, Transition::Epsilon);
+            Regex::Alt(_, _) => {
+                let start = self.new_state();
+                let mut holes = Vec::new();
+                for part in chain(regex) {
+                    let branch = self.build(part);
+                    self.add_transition(start, branch.start, Transition::Epsilon);
+                    holes.extend(branch.holes);
+                }
+                Fragment { start, holes }
+            }
 
-            nfa.start = start;
-            nfa.accept = accept;
-            nfa
-        }
+            Regex::Star(inner) => {
+                let start = self.new_state();
+                let body = self.build(inner);
+                self.add_transition(start, body.start, Transition::Epsilon);
+                self.patch(body.holes, start);
+                let hole = self.dangling(start, Transition::Epsilon);
+                Fragment {
+                    start,
+                    holes: vec![hole],
+                }
+            }
 
-        Regex::Star(inner) => {
-            let base = from_regex(inner);
-            let mut nfa = NFA {
-                states: base.states.clone(),
-                start: 0,
-                accept: 0,
-            };
-            let start = nfa.new_state();
-            let accept = nfa.new_state();
-            nfa.start = start;
-            nfa.accept = accept;
-
-            nfa.add_transition(start, base.start, Transition::Epsilon);
-            nfa.add_transition(start, accept, Transition::Epsilon);
-            nfa.add_transition(base.accept, base.start, Transition::Epsilon);
-            nfa.add_transition(base.accept, accept, Transition::Epsilon);
-
-            nfa
-        }
+            Regex::Plus(inner) => {
+                let body = self.build(inner);
+                let repeat = self.new_state();
+                self.patch(body.holes, repeat);
+                self.add_transition(repeat, body.start, Transition::Epsilon);
+                let hole = self.dangling(repeat, Transition::Epsilon);
+                Fragment {
+                    start: body.start,
+                    holes: vec![hole],
+                }
+            }
 
-        Regex::Plus(inner) => {
-            let base = from_regex(inner);
-            let mut nfa = NFA {
-                states: base.states.clone(),
-                start: 0,
-                accept: 0,
-            };
-            let start = nfa.new_state();
-            let accept = nfa.new_state();
-            nfa.start = start;
-            nfa.accept = accept;
-
-            nfa.add_transition(start, base.start, Transition::Epsilon);
-            nfa.add_transition(base.accept, base.start, Transition::Epsilon);
-            nfa.add_transition(base.accept, accept, Transition::Epsilon);
-
-            nfa
+            Regex::Optional(inner) => {
+                let start = self.new_state();
+                let body = self.build(inner);
+                self.add_transition(start, body.start, Transition::Epsilon);
+                let mut holes = body.holes;
+                holes.push(self.dangling(start, Transition::Epsilon));
+                Fragment { start, holes }
+            }
         }
+    }
+}
 
-        Regex::Optional(inner) => {
-            let base = from_regex(inner);
-            let mut nfa = NFA {
-                states: base.states.clone(),
-                start: 0,
-                accept: 0,
-            };
-            let start = nfa.new_state();
-            let accept = nfa.new_state();
-            nfa.start = start;
-            nfa.accept = accept;
-
-            nfa.add_transition(start, base.start, Transition::Epsilon);
-            nfa.add_transition(start, accept, Transition::Epsilon);
-            nfa.add_transition(base.accept, accept, Transition::Epsilon);
-
-            nfa
-        }
+/// The operands of a left-nested run of `Concat` or `Alt` nodes, in order.
+/// Collecting them in a loop rather than recursing once per node keeps long
+/// literals from exhausting the stack.
+pub(crate) fn chain(regex: &Regex) -> Vec<&Regex> {
+    let mut parts = Vec::new();
+    let mut node = regex;
+    while let (Regex::Concat(left, right), Regex::Concat(_, _))
+    | (Regex::Alt(left, right), Regex::Alt(_, _)) = (node, regex)
+    {
+        parts.push(&**right);
+        node = left;
+    }
+    parts.push(node);
+    parts.reverse();
+    parts
+}
+
+pub fn from_regex(regex: &Regex) -> NFA {
+    let mut builder = Builder { states: vec![] };
+    let fragment = builder.build(regex);
+    let accept = builder.new_state();
+    builder.patch(fragment.holes, accept);
+
+    NFA {
+        states: builder.states,
+        start: fragment.start,
+        accepts: vec![vec![accept]],
+    }
+}
+
+/// One NFA for several patterns: a start state with an epsilon edge to
+/// each of them, and an accept state per pattern, so that pattern `i`
+/// accepts in `accepts[i][0]`.
+pub fn from_regexes(regexes: &[Regex]) -> NFA {
+    let mut builder = Builder { states: vec![] };
+    let start = builder.new_state();
+    let mut accepts = Vec::with_capacity(regexes.len());
+    for regex in regexes {
+        let fragment = builder.build(regex);
+        builder.add_transition(start, fragment.start, Transition::Epsilon);
+        let accept = builder.new_state();
+        builder.patch(fragment.holes, accept);
+        accepts.push(vec![accept]);
+    }
+
+    NFA {
+        states: builder.states,
+        start,
+        accepts,
     }
 }
 
@@ -297,12 +609,42 @@ mod structure_tests {
                 State { edges: vec![] },
             ],
             start: 0,
-            accept: 1,
+            accepts: vec![vec![1]],
         };
 
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn from_regex_dot_and_class_structure() {
+        let dot = from_regex(&Dot);
+        assert_eq!(
+            dot.states[0].edges,
+            vec![Edge {
+                label: Transition::Range(0, 255),
+                to: 1,
+            }]
+        );
+
+        let class = from_regex(&Class(vec![(b'0', b'9'), (b'a', b'f')]));
+        assert_eq!(class.states.len(), 2);
+        assert_eq!(
+            class.states[0]
+                .edges
+                .iter()
+                .map(|e| &e.label)
+                .collect::<Vec<_>>(),
+            vec![
+                &Transition::Range(b'0', b'9'),
+                &Transition::Range(b'a', b'f')
+            ]
+        );
+        // both ranges lead to the same place, so they share a class
+        let classes = class.byte_classes();
+        assert_eq!(classes.count(), 2);
+        assert_eq!(classes.get(b'0'), classes.get(b'f'));
+    }
+
     #[test]
     fn from_regex_concat_structure() {
         let actual = from_regex(&Concat(b(Byte(b'x')), b(Byte(b'y'))));
@@ -315,22 +657,16 @@ mod structure_tests {
                         to: 1,
                     }],
                 },
-                State {
-                    edges: vec![Edge {
-                        label: Transition::Epsilon,
-                        to: 2,
-                    }],
-                },
                 State {
                     edges: vec![Edge {
                         label: Transition::Byte(b'y'),
-                        to: 3,
+                        to: 2,
                     }],
                 },
                 State { edges: vec![] },
             ],
             start: 0,
-            accept: 3,
+            accepts: vec![vec![2]],
         };
 
         assert_eq!(actual, expected);
@@ -351,43 +687,29 @@ mod structure_tests {
                         },
                         Edge {
                             label: Transition::Epsilon,
-                            to: 3,
+                            to: 2,
                         },
                     ],
                 },
-                // state 1: a start
+                // state 1: a
                 State {
                     edges: vec![Edge {
                         label: Transition::Byte(b'a'),
-                        to: 2,
-                    }],
-                },
-                // state 2: a accept
-                State {
-                    edges: vec![Edge {
-                        label: Transition::Epsilon,
-                        to: 5,
+                        to: 3,
                     }],
                 },
-                // state 3: b start
+                // state 2: b
                 State {
                     edges: vec![Edge {
                         label: Transition::Byte(b'b'),
-                        to: 4,
-                    }],
-                },
-                // state 4: b accept
-                State {
-                    edges: vec![Edge {
-                        label: Transition::Epsilon,
-                        to: 5,
+                        to: 3,
                     }],
                 },
-                // state 5: final accept
+                // state 3: final accept
                 State { edges: vec![] },
             ],
             start: 0,
-            accept: 5,
+            accepts: vec![vec![3]],
         };
 
         assert_eq!(actual, expected);
@@ -399,24 +721,45 @@ mod structure_tests {
 
         let expected = NFA {
             states: vec![
-                State {
-                    edges: vec![Edge {
-                        label: Transition::Byte(b'z'),
-                        to: 1,
-                    }],
-                },
                 State {
                     edges: vec![
                         Edge {
                             label: Transition::Epsilon,
-                            to: 0,
+                            to: 1,
                         },
                         Edge {
                             label: Transition::Epsilon,
-                            to: 3,
+                            to: 2,
                         },
                     ],
                 },
+                State {
+                    edges: vec![Edge {
+                        label: Transition::Byte(b'z'),
+                        to: 0,
+                    }],
+                },
+                State { edges: vec![] },
+            ],
+            start: 0,
+            accepts: vec![vec![2]],
+        };
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn from_regex_plus_structure() {
+        let actual = from_regex(&Plus(Box::new(Byte(b'z'))));
+
+        let expected = NFA {
+            states: vec![
+                State {
+                    edges: vec![Edge {
+                        label: Transition::Byte(b'z'),
+                        to: 1,
+                    }],
+                },
                 State {
                     edges: vec![
                         Edge {
@@ -425,16 +768,27 @@ mod structure_tests {
                         },
                         Edge {
                             label: Transition::Epsilon,
-                            to: 3,
+                            to: 2,
                         },
                     ],
                 },
                 State { edges: vec![] },
             ],
-            start: 2,
-            accept: 3,
+            start: 0,
+            accepts: vec![vec![2]],
         };
 
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn from_regex_is_linear_in_the_ast() {
+        // about 20k nodes: 100 nested stars, each followed by a long literal
+        let literal = (0..99).fold(Byte(b'a'), |acc, _| Concat(b(acc), b(Byte(b'b'))));
+        let ast = (0..100).fold(literal.clone(), |acc, _| {
+            Concat(b(Star(b(acc))), b(literal.clone()))
+        });
+        let nfa = from_regex(&ast);
+        assert!(nfa.states.len() <= ast.node_count() + 1);
+    }
 }
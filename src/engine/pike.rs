@@ -0,0 +1,285 @@
+use std::mem;
+
+use crate::{
+    ast::Regex,
+    engine::nfa::{self, Transition, NFA},
+    parser::parser::Anchors,
+};
+
+/// No thread is in the state.
+const NONE: usize = usize::MAX;
+
+/// The threads of a Pike VM run of an NFA, each remembering where its match
+/// started. Of several threads reaching one state only the one that
+/// started leftmost is kept, since the others can only end where it can.
+/// A step visits each state at most once, so scanning a haystack takes
+/// O(n·m) time for n bytes and m states, however long the matches are.
+pub(crate) struct Threads {
+    current: ThreadSet,
+    next: ThreadSet,
+    stack: Vec<usize>,
+}
+
+/// States with a thread, in order of where the threads started.
+struct ThreadSet {
+    states: Vec<usize>,
+    /// The start of the thread in each state, or `NONE`.
+    starts: Vec<usize>,
+}
+
+impl ThreadSet {
+    fn new(len: usize) -> Self {
+        Self {
+            states: Vec::new(),
+            starts: vec![NONE; len],
+        }
+    }
+
+    fn clear(&mut self) {
+        for &state in &self.states {
+            self.starts[state] = NONE;
+        }
+        self.states.clear();
+    }
+
+    /// Adds a thread in `state` and every state it reaches without reading,
+    /// unless one is there already; threads are added in order of start, so
+    /// that one started no later.
+    fn add(&mut self, stack: &mut Vec<usize>, nfa: &NFA, state: usize, start: usize) {
+        stack.push(state);
+        while let Some(state) = stack.pop() {
+            if self.starts[state] != NONE {
+                continue;
+            }
+            self.starts[state] = start;
+            self.states.push(state);
+            for edge in nfa.states[state].edges.iter().rev() {
+                if let Transition::Epsilon = edge.label {
+                    stack.push(edge.to);
+                }
+            }
+        }
+    }
+}
+
+impl Threads {
+    pub(crate) fn new(nfa: &NFA) -> Self {
+        Self {
+            current: ThreadSet::new(nfa.states.len()),
+            next: ThreadSet::new(nfa.states.len()),
+            stack: Vec::new(),
+        }
+    }
+
+    /// Starts a thread at position `start` in `state`. It must not start
+    /// before any thread already running.
+    pub(crate) fn add(&mut self, nfa: &NFA, state: usize, start: usize) {
+        self.current.add(&mut self.stack, nfa, state, start);
+    }
+
+    /// Moves every thread over `byte`, dropping those that cannot read it.
+    pub(crate) fn step(&mut self, nfa: &NFA, byte: u8) {
+        for &state in &self.current.states {
+            let start = self.current.starts[state];
+            for edge in &nfa.states[state].edges {
+                if edge.label.accepts(byte) {
+                    self.next.add(&mut self.stack, nfa, edge.to, start);
+                }
+            }
+        }
+        self.current.clear();
+        mem::swap(&mut self.current, &mut self.next);
+    }
+
+    /// Where the thread in `state` started, if there is one.
+    pub(crate) fn start(&self, state: usize) -> Option<usize> {
+        Some(self.current.starts[state]).filter(|&start| start != NONE)
+    }
+
+    /// Where the leftmost thread started, if any is running.
+    pub(crate) fn earliest(&self) -> Option<usize> {
+        let &state = self.current.states.first()?;
+        Some(self.current.starts[state])
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.current.states.is_empty()
+    }
+
+    /// Drops the threads that started after `start`.
+    pub(crate) fn retain_until(&mut self, start: usize) {
+        let current = &mut self.current;
+        let keep = current
+            .states
+            .partition_point(|&state| current.starts[state] <= start);
+        for &state in &current.states[keep..] {
+            current.starts[state] = NONE;
+        }
+        current.states.truncate(keep);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.current.clear();
+    }
+}
+
+/// Patterns in one NFA for searching with threads. Each is made of one or
+/// more branches, which ERE anchors may hold to the start or end of the
+/// haystack.
+#[derive(Debug)]
+pub(crate) struct Program {
+    nfa: NFA,
+    /// The pattern and anchors of each branch, in order of pattern.
+    branches: Vec<(usize, Anchors)>,
+    /// Where threads start past the start of the haystack: the NFA's start,
+    /// or if some branch is held to the start, the starts of the others.
+    entries: Vec<usize>,
+}
+
+impl Program {
+    /// One unanchored pattern per regex.
+    pub(crate) fn new(regexes: &[Regex]) -> Self {
+        Self::unanchored(nfa::from_regexes(regexes))
+    }
+
+    /// A single unanchored pattern, without the extra start state of
+    /// several.
+    pub(crate) fn single(regex: &Regex) -> Self {
+        Self::unanchored(nfa::from_regex(regex))
+    }
+
+    fn unanchored(nfa: NFA) -> Self {
+        Self {
+            entries: vec![nfa.start],
+            branches: (0..nfa.accepts.len())
+                .map(|pattern| (pattern, Anchors::default()))
+                .collect(),
+            nfa,
+        }
+    }
+
+    /// A single pattern made of the given branches.
+    pub(crate) fn anchored(branches: &[(Regex, Anchors)]) -> Self {
+        let regexes: Vec<_> = branches.iter().map(|(regex, _)| regex.clone()).collect();
+        let nfa = nfa::from_regexes(&regexes);
+        let edges = &nfa.states[nfa.start].edges;
+        Self {
+            entries: edges
+                .iter()
+                .zip(branches)
+                .filter(|(_, (_, anchors))| !anchors.start)
+                .map(|(edge, _)| edge.to)
+                .collect(),
+            branches: branches.iter().map(|&(_, anchors)| (0, anchors)).collect(),
+            nfa,
+        }
+    }
+
+    pub(crate) fn threads(&self) -> Threads {
+        Threads::new(&self.nfa)
+    }
+
+    /// Starts threads at `at` in the branches that may start there.
+    pub(crate) fn enter(&self, threads: &mut Threads, at: usize) {
+        if at == 0 {
+            threads.add(&self.nfa, self.nfa.start, 0);
+        } else {
+            for &state in &self.entries {
+                threads.add(&self.nfa, state, at);
+            }
+        }
+    }
+
+    pub(crate) fn step(&self, threads: &mut Threads, byte: u8) {
+        threads.step(&self.nfa, byte);
+    }
+
+    /// The pattern and start of each match ending where the threads are,
+    /// one per branch, in order of pattern. `at_end` is whether that is
+    /// the end of the haystack.
+    pub(crate) fn matches<'a>(
+        &'a self,
+        threads: &'a Threads,
+        at_end: bool,
+    ) -> impl Iterator<Item = (usize, usize)> + 'a {
+        self.branches
+            .iter()
+            .zip(&self.nfa.accepts)
+            .filter(move |((_, anchors), _)| at_end || !anchors.end)
+            .filter_map(|(&(pattern, _), accepts)| {
+                let start = accepts.iter().filter_map(|&accept| threads.start(accept));
+                Some((pattern, start.min()?))
+            })
+    }
+
+    /// The leftmost start of a match ending where the threads are.
+    pub(crate) fn accepted(&self, threads: &Threads, at_end: bool) -> Option<usize> {
+        // The common case, checked once per byte
+        if let [accepts] = &self.nfa.accepts[..] {
+            if let [accept] = accepts[..] {
+                return threads
+                    .start(accept)
+                    .filter(|_| at_end || !self.branches[0].1.end);
+            }
+        }
+        self.matches(threads, at_end).map(|(_, start)| start).min()
+    }
+
+    /// The leftmost-longest match starting at or after `at`.
+    pub(crate) fn find_at(&self, haystack: &[u8], at: usize) -> Option<(usize, usize)> {
+        // Past an empty match at the end, there is nothing left to search
+        if at > haystack.len() {
+            return None;
+        }
+        let mut threads = self.threads();
+        let mut best = None;
+        let mut pos = at;
+        loop {
+            if best.is_none() {
+                self.enter(&mut threads, pos);
+            }
+            if let Some(start) = self.accepted(&threads, pos == haystack.len()) {
+                // Threads left are leftmost, so any later match is better
+                best = Some((start, pos));
+                threads.retain_until(start);
+            }
+            if pos == haystack.len() || threads.is_empty() {
+                return best;
+            }
+            self.step(&mut threads, haystack[pos]);
+            pos += 1;
+        }
+    }
+
+    /// Whether any match is in `haystack`, stopping at the first end of
+    /// one.
+    pub(crate) fn is_match(&self, haystack: &[u8]) -> bool {
+        let mut threads = self.threads();
+        for pos in 0..=haystack.len() {
+            if pos > 0 {
+                self.step(&mut threads, haystack[pos - 1]);
+            }
+            self.enter(&mut threads, pos);
+            if self.accepted(&threads, pos == haystack.len()).is_some() {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// The match that ends last, extended as far left as it goes.
+    pub(crate) fn rfind(&self, haystack: &[u8]) -> Option<(usize, usize)> {
+        let mut threads = self.threads();
+        let mut last = None;
+        for end in 0..=haystack.len() {
+            if end > 0 {
+                self.step(&mut threads, haystack[end - 1]);
+            }
+            self.enter(&mut threads, end);
+            if let Some(start) = self.accepted(&threads, end == haystack.len()) {
+                last = Some((start, end));
+            }
+        }
+        last
+    }
+}
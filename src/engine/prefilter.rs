@@ -0,0 +1,368 @@
+use memchr::memmem;
+
+use crate::{
+    ast::Regex,
+    engine::{
+        compiler,
+        dfa::{StateKind, DFA},
+        nfa::chain,
+    },
+    error::Limits,
+};
+
+/// Literals are cut short at this length.
+const MAX_LITERAL_LEN: usize = 16;
+/// Past this many literals, a set is no longer worth scanning for.
+const MAX_LITERALS: usize = 32;
+/// Classes with more bytes than this are not expanded into literals.
+const MAX_CLASS_BYTES: usize = 16;
+
+/// A string that a match must start with. If `exact`, the sub-expression
+/// it came from matches exactly this string, so whatever follows can be
+/// appended to it.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Literal {
+    bytes: Vec<u8>,
+    exact: bool,
+}
+
+impl Literal {
+    fn exact(bytes: Vec<u8>) -> Self {
+        Self { bytes, exact: true }
+    }
+
+    fn inexact(bytes: Vec<u8>) -> Self {
+        Self {
+            bytes,
+            exact: false,
+        }
+    }
+}
+
+/// A set every match starts with a member of; `vec![Literal::inexact(vec![])]`
+/// when nothing useful is known.
+fn prefixes(regex: &Regex) -> Vec<Literal> {
+    let anything = || vec![Literal::inexact(vec![])];
+    match regex {
+        Regex::Empty => vec![Literal::exact(vec![])],
+        Regex::Byte(b) => vec![Literal::exact(vec![*b])],
+        Regex::Class(ranges) => {
+            let count: usize = ranges.iter().map(|&(lo, hi)| (hi - lo) as usize + 1).sum();
+            if count > MAX_CLASS_BYTES {
+                return anything();
+            }
+            ranges
+                .iter()
+                .flat_map(|&(lo, hi)| lo..=hi)
+                .map(|b| Literal::exact(vec![b]))
+                .collect()
+        }
+        Regex::Dot | Regex::Star(_) => anything(),
+
+        Regex::Concat(_, _) => {
+            let mut parts = chain(regex).into_iter();
+            let mut acc = prefixes(parts.next().unwrap());
+            for part in parts {
+                if acc.iter().all(|lit| !lit.exact) {
+                    break;
+                }
+                acc = cross(acc, prefixes(part));
+            }
+            acc
+        }
+
+        Regex::Alt(_, _) => {
+            let mut acc = Vec::new();
+            for part in chain(regex) {
+                acc.extend(prefixes(part));
+                if acc.len() > MAX_LITERALS {
+                    return anything();
+                }
+            }
+            normalize(acc)
+        }
+
+        Regex::Plus(inner) => prefixes(inner)
+            .into_iter()
+            .map(|lit| Literal::inexact(lit.bytes))
+            .collect(),
+
+        Regex::Optional(inner) => {
+            let mut lits = prefixes(inner);
+            lits.push(Literal::exact(vec![]));
+            normalize(lits)
+        }
+    }
+}
+
+/// Extends each exact literal in `left` with each literal in `right`. If
+/// that would make too many, `left` is returned with nothing extended.
+fn cross(left: Vec<Literal>, right: Vec<Literal>) -> Vec<Literal> {
+    let exact = left.iter().filter(|lit| lit.exact).count();
+    if (left.len() - exact) + exact * right.len() > MAX_LITERALS {
+        return left
+            .into_iter()
+            .map(|lit| Literal::inexact(lit.bytes))
+            .collect();
+    }
+
+    let mut out = Vec::new();
+    for lit in left {
+        if !lit.exact {
+            out.push(lit);
+            continue;
+        }
+        for next in &right {
+            let mut bytes = lit.bytes.clone();
+            bytes.extend_from_slice(&next.bytes);
+            out.push(Literal {
+                bytes,
+                exact: next.exact,
+            });
+        }
+    }
+    normalize(out)
+}
+
+fn normalize(mut lits: Vec<Literal>) -> Vec<Literal> {
+    for lit in &mut lits {
+        if lit.bytes.len() > MAX_LITERAL_LEN {
+            lit.bytes.truncate(MAX_LITERAL_LEN);
+            lit.exact = false;
+        }
+    }
+    lits.sort();
+    lits.dedup();
+    lits
+}
+
+/// Finds where a match could start without running the automaton, using
+/// literals every match must begin with.
+#[derive(Debug, Clone)]
+pub enum Prefilter {
+    /// Every match starts with this substring.
+    Substring(Box<memmem::Finder<'static>>),
+    /// Every match starts with one of these bytes.
+    Byte(u8),
+    Byte2(u8, u8),
+    Byte3(u8, u8, u8),
+    ByteSet(Box<[bool; 256]>),
+}
+
+impl Prefilter {
+    /// Returns `None` when matches can start with anything, including
+    /// when the pattern matches the empty string.
+    pub fn new(ast: &Regex) -> Option<Self> {
+        let lits = prefixes(ast);
+        if lits.is_empty() || lits.iter().any(|lit| lit.bytes.is_empty()) {
+            return None;
+        }
+
+        let common = lits
+            .iter()
+            .map(|lit| lit.bytes.as_slice())
+            .reduce(|a, b| {
+                let n = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+                &a[..n]
+            })
+            .unwrap();
+        if common.len() >= 2 || lits.len() == 1 {
+            return Some(Prefilter::Substring(Box::new(
+                memmem::Finder::new(common).into_owned(),
+            )));
+        }
+
+        let mut firsts: Vec<u8> = lits.iter().map(|lit| lit.bytes[0]).collect();
+        firsts.sort_unstable();
+        firsts.dedup();
+        Some(match *firsts.as_slice() {
+            [a] => Prefilter::Byte(a),
+            [a, b] => Prefilter::Byte2(a, b),
+            [a, b, c] => Prefilter::Byte3(a, b, c),
+            _ => {
+                let mut set = Box::new([false; 256]);
+                for b in firsts {
+                    set[b as usize] = true;
+                }
+                Prefilter::ByteSet(set)
+            }
+        })
+    }
+
+    /// How many bytes from a position `find` looks at, so a stream
+    /// searched in pieces must keep that many less one from the last.
+    pub fn width(&self) -> usize {
+        match self {
+            Prefilter::Substring(finder) => finder.needle().len(),
+            _ => 1,
+        }
+    }
+
+    /// The first position at or after `at` where a match could start.
+    pub fn find(&self, haystack: &[u8], at: usize) -> Option<usize> {
+        let rest = haystack.get(at..)?;
+        let found = match self {
+            Prefilter::Substring(finder) => finder.find(rest),
+            Prefilter::Byte(a) => memchr::memchr(*a, rest),
+            Prefilter::Byte2(a, b) => memchr::memchr2(*a, *b, rest),
+            Prefilter::Byte3(a, b, c) => memchr::memchr3(*a, *b, *c, rest),
+            Prefilter::ByteSet(set) => rest.iter().position(|&b| set[b as usize]),
+        };
+        found.map(|i| at + i)
+    }
+}
+
+/// A literal that every match contains after some non-empty part of the
+/// pattern, such as the `@example` in `[a-z]+@example`. Searching for it
+/// rules out haystacks without it at once, and a reverse DFA for the part
+/// before it then finds where the match starts.
+#[derive(Debug)]
+pub struct InnerLiteral {
+    finder: Box<memmem::Finder<'static>>,
+    reverse_prefix: DFA,
+}
+
+impl InnerLiteral {
+    /// Takes the longest run of bytes in the top-level concatenation that
+    /// is not at its start. Returns `None` if there is no such run, if the
+    /// reverse DFA would exceed `limits`, or if the part before the run can
+    /// match its first byte.
+    pub fn new(ast: &Regex, limits: &Limits) -> Option<Self> {
+        if !matches!(ast, Regex::Concat(_, _)) {
+            return None;
+        }
+        let parts = chain(ast);
+        let mut best = None;
+        let mut i = 1;
+        while i < parts.len() {
+            let j = (i..parts.len())
+                .find(|&j| !matches!(parts[j], Regex::Byte(_)))
+                .unwrap_or(parts.len());
+            if j - i > best.map_or(0, |(i, j)| j - i) {
+                best = Some((i, j));
+            }
+            i = j + 1;
+        }
+
+        let (i, j) = best?;
+        let literal: Vec<u8> = parts[i..j]
+            .iter()
+            .map(|part| match part {
+                Regex::Byte(b) => *b,
+                _ => unreachable!(),
+            })
+            .collect();
+        let prefix = parts[..i]
+            .iter()
+            .map(|&part| part.clone())
+            .reduce(|acc, part| Regex::Concat(Box::new(acc), Box::new(part)))?;
+        let reverse_prefix = compiler::compile_reverse(&prefix, limits).ok()?;
+        // Otherwise a match could start before the one found at an
+        // occurrence, with its prefix running over that occurrence
+        if reads(&reverse_prefix, literal[0]) {
+            return None;
+        }
+
+        Some(Self {
+            finder: Box::new(memmem::Finder::new(&literal).into_owned()),
+            reverse_prefix,
+        })
+    }
+
+    /// The leftmost-longest match starting at or after `at`, where
+    /// `longest` gives the length of the longest match of the whole
+    /// pattern at the start of its input.
+    pub fn find(
+        &self,
+        haystack: &[u8],
+        at: usize,
+        longest: impl Fn(&[u8]) -> Option<usize>,
+    ) -> Option<(usize, usize)> {
+        let mut from = at;
+        loop {
+            let p = from + self.finder.find(haystack.get(from..)?)?;
+            if let Some(n) = self.reverse_prefix.longest_match_rev(&haystack[at..p]) {
+                let start = p - n;
+                if let Some(len) = longest(&haystack[start..]) {
+                    return Some((start, start + len));
+                }
+            }
+            // Every match contains the literal with its prefix right before
+            // it, so an occurrence that fails either check is no use.
+            from = p + 1;
+        }
+    }
+}
+
+/// Whether some string `dfa` matches contains `byte`.
+fn reads(dfa: &DFA, byte: u8) -> bool {
+    let class = dfa.classes.get(byte);
+    (0..dfa.state_count()).any(|state| {
+        dfa.kind(state) != StateKind::Dead && dfa.kind(dfa.next(state, class)) != StateKind::Dead
+    })
+}
+
+#[cfg(test)]
+mod prefilter_tests {
+    use super::*;
+    use crate::parser::parser::parse;
+
+    fn lits(pattern: &str) -> Vec<(String, bool)> {
+        prefixes(&parse(pattern))
+            .into_iter()
+            .map(|lit| (String::from_utf8(lit.bytes).unwrap(), lit.exact))
+            .collect()
+    }
+
+    #[test]
+    fn literal_prefix_of_concat() {
+        assert_eq!(lits("abc"), vec![("abc".to_string(), true)]);
+        assert_eq!(lits("ab.*c"), vec![("ab".to_string(), false)]);
+        assert_eq!(lits("ab+c"), vec![("ab".to_string(), false)]);
+    }
+
+    #[test]
+    fn alternation_and_optional_expand() {
+        assert_eq!(
+            lits("(GET|POST)x"),
+            vec![("GETx".to_string(), true), ("POSTx".to_string(), true)]
+        );
+        assert_eq!(
+            lits("ab?c"),
+            vec![("abc".to_string(), true), ("ac".to_string(), true)]
+        );
+    }
+
+    #[test]
+    fn unknown_prefixes() {
+        assert_eq!(lits(".abc"), vec![(String::new(), false)]);
+        assert_eq!(lits("a*b"), vec![(String::new(), false)]);
+        assert!(Prefilter::new(&parse("a?")).is_none());
+    }
+
+    #[test]
+    fn long_literals_are_cut() {
+        let pattern = "a".repeat(40);
+        assert_eq!(lits(&pattern), vec![("a".repeat(MAX_LITERAL_LEN), false)]);
+    }
+
+    #[test]
+    fn scanner_choice() {
+        let find = |pattern: &str, haystack: &str| {
+            Prefilter::new(&parse(pattern))
+                .unwrap()
+                .find(haystack.as_bytes(), 1)
+        };
+        assert!(matches!(
+            Prefilter::new(&parse("ab(c|d)")),
+            Some(Prefilter::Substring(_))
+        ));
+        assert!(matches!(
+            Prefilter::new(&parse("a|b")),
+            Some(Prefilter::Byte2(b'a', b'b'))
+        ));
+        assert_eq!(find("ab(c|d)", "abxxabd"), Some(4));
+        assert_eq!(find("a|b|c|d", "axxd"), Some(3));
+        assert_eq!(find("xyz", "xyxy"), None);
+    }
+}
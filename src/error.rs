@@ -0,0 +1,70 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// The pattern is not valid in its syntax.
+    Syntax(String),
+    /// Groups or operators are nested deeper than `Limits::max_depth`.
+    NestingTooDeep { limit: usize },
+    /// The pattern has more AST nodes than `Limits::max_ast_nodes`.
+    TooManyAstNodes { limit: usize },
+    /// Thompson construction needs more than `Limits::max_nfa_states`.
+    TooManyNfaStates { limit: usize },
+    /// Subset construction needs more than `Limits::max_dfa_states`.
+    TooManyDfaStates { limit: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Syntax(msg) => write!(f, "{}", msg),
+            Error::NestingTooDeep { limit } => {
+                write!(f, "pattern nesting exceeds the limit of {}", limit)
+            }
+            Error::TooManyAstNodes { limit } => {
+                write!(f, "pattern exceeds the limit of {} AST nodes", limit)
+            }
+            Error::TooManyNfaStates { limit } => {
+                write!(f, "NFA exceeds the limit of {} states", limit)
+            }
+            Error::TooManyDfaStates { limit } => {
+                write!(f, "DFA exceeds the limit of {} states", limit)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Bounds on the resources a single pattern may use during compilation.
+/// The defaults are generous for hand-written patterns while keeping
+/// hostile ones from overflowing the stack or exhausting memory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Limits {
+    pub max_depth: usize,
+    pub max_ast_nodes: usize,
+    pub max_nfa_states: usize,
+    pub max_dfa_states: usize,
+}
+
+impl Limits {
+    pub fn unlimited() -> Self {
+        Self {
+            max_depth: usize::MAX,
+            max_ast_nodes: usize::MAX,
+            max_nfa_states: usize::MAX,
+            max_dfa_states: usize::MAX,
+        }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_depth: 250,
+            max_ast_nodes: 10_000,
+            max_nfa_states: 1_000_000,
+            max_dfa_states: 10_000,
+        }
+    }
+}
@@ -0,0 +1,301 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::{ast::Regex, error::Error};
+
+#[derive(Debug, Clone, Default)]
+pub struct GlobOptions {
+    /// Match letters regardless of case. Ranges in `[..]` are only folded
+    /// for ASCII letters.
+    pub case_insensitive: bool,
+    /// Require a leading `.` in a path component to be matched literally,
+    /// so `*`, `?`, `[..]` and `**` never match hidden files.
+    pub literal_leading_dot: bool,
+}
+
+/// Translates a shell glob into a regex that matches whole paths.
+///
+/// `*` and `?` never cross a `/`, while `**` as a whole path component
+/// matches any number of directories. `[..]` (negated with `!` or `^`) and
+/// `{a,b}` alternation may be nested inside braces.
+pub fn parse(pattern: &str) -> Result<Regex, Error> {
+    parse_with(pattern, &GlobOptions::default())
+}
+
+pub fn parse_with(pattern: &str, options: &GlobOptions) -> Result<Regex, Error> {
+    let mut parser = GlobParser {
+        chars: pattern.chars().peekable(),
+        options,
+    };
+    let (regex, _) = parser.parse_seq(false, true)?;
+    match parser.chars.next() {
+        None => Ok(regex),
+        Some(c) => Err(Error::Syntax(format!("Unexpected '{}' in glob", c))),
+    }
+}
+
+struct GlobParser<'a, 'o> {
+    chars: Peekable<Chars<'a>>,
+    options: &'o GlobOptions,
+}
+
+impl GlobParser<'_, '_> {
+    /// Parses until the end of input or, inside braces, an unescaped `,` or
+    /// `}`. Returns the regex and whether it ended at the start of a path
+    /// component.
+    fn parse_seq(&mut self, in_brace: bool, mut at_start: bool) -> Result<(Regex, bool), Error> {
+        let mut items = Vec::new();
+
+        while let Some(&c) = self.chars.peek() {
+            if in_brace && (c == ',' || c == '}') {
+                break;
+            }
+            self.chars.next();
+
+            match c {
+                '*' => {
+                    if self.chars.peek() == Some(&'*') {
+                        while self.chars.peek() == Some(&'*') {
+                            self.chars.next();
+                        }
+                        let next = self.chars.peek().copied();
+                        let ends_component = match next {
+                            None => true,
+                            Some('/') => true,
+                            Some(',') | Some('}') => in_brace,
+                            _ => false,
+                        };
+                        if at_start && ends_component {
+                            if next == Some('/') {
+                                self.chars.next();
+                                items.push(self.component().then('/').star());
+                            } else {
+                                items.push(self.any_path());
+                            }
+                            continue;
+                        }
+                    }
+                    items.push(self.star(at_start));
+                    at_start = false;
+                }
+                '?' => {
+                    items.push(self.single(at_start));
+                    at_start = false;
+                }
+                '[' => {
+                    items.push(self.parse_class(at_start)?);
+                    at_start = false;
+                }
+                '{' => {
+                    let (alts, ends_at_start) = self.parse_brace(at_start)?;
+                    items.push(alts);
+                    at_start = ends_at_start;
+                }
+                '\\' => match self.chars.next() {
+                    Some(escaped) => {
+                        items.push(self.char(escaped));
+                        at_start = escaped == '/';
+                    }
+                    None => return Err(Error::Syntax("Trailing '\\' in glob".to_string())),
+                },
+                _ => {
+                    items.push(self.char(c));
+                    at_start = c == '/';
+                }
+            }
+        }
+
+        let regex = items
+            .into_iter()
+            .reduce(Regex::then)
+            .unwrap_or(Regex::Empty);
+        Ok((regex, at_start))
+    }
+
+    fn parse_brace(&mut self, at_start: bool) -> Result<(Regex, bool), Error> {
+        let mut alts = Vec::new();
+        let mut ends_at_start = true;
+
+        loop {
+            let (alt, alt_at_start) = self.parse_seq(true, at_start)?;
+            alts.push(alt);
+            ends_at_start &= alt_at_start;
+
+            match self.chars.next() {
+                Some(',') => continue,
+                Some('}') => break,
+                _ => return Err(Error::Syntax("Unclosed '{' in glob".to_string())),
+            }
+        }
+
+        Ok((Regex::any_of(alts), ends_at_start))
+    }
+
+    fn parse_class(&mut self, at_start: bool) -> Result<Regex, Error> {
+        let negated = matches!(self.chars.peek(), Some('!') | Some('^'));
+        if negated {
+            self.chars.next();
+        }
+
+        let mut ranges: Vec<(u32, u32)> = Vec::new();
+        let mut first = true;
+        loop {
+            let lo = match self.chars.next() {
+                None => return Err(unclosed_class()),
+                Some(']') if !first => break,
+                Some('\\') => self.chars.next().ok_or_else(unclosed_class)?,
+                Some(c) => c,
+            };
+            first = false;
+
+            let mut lookahead = self.chars.clone();
+            let hi = match (lookahead.next(), lookahead.next()) {
+                (Some('-'), Some(hi)) if hi != ']' => {
+                    self.chars.next();
+                    self.chars.next();
+                    if hi == '\\' {
+                        self.chars.next().ok_or_else(unclosed_class)?
+                    } else {
+                        hi
+                    }
+                }
+                _ => lo,
+            };
+            if lo > hi {
+                return Err(Error::Syntax(format!(
+                    "Invalid range '{}-{}' in glob",
+                    lo, hi
+                )));
+            }
+            ranges.push((lo as u32, hi as u32));
+        }
+
+        if self.options.case_insensitive {
+            ranges = fold_ascii(&ranges);
+        }
+        if negated {
+            ranges = complement(&ranges);
+        }
+        ranges = subtract(&ranges, '/');
+        if at_start && self.options.literal_leading_dot {
+            ranges = subtract(&ranges, '.');
+        }
+
+        Ok(Regex::class_of(ranges.into_iter().flat_map(to_chars)))
+    }
+
+    fn char(&self, c: char) -> Regex {
+        if self.options.case_insensitive {
+            Regex::literal_ignore_case(c.encode_utf8(&mut [0; 4]))
+        } else {
+            Regex::from(c)
+        }
+    }
+
+    /// Any single char that `?` may match.
+    fn single(&self, at_start: bool) -> Regex {
+        let mut ranges = subtract(&[(0, char::MAX as u32)], '/');
+        if at_start && self.options.literal_leading_dot {
+            ranges = subtract(&ranges, '.');
+        }
+        Regex::class_of(ranges.into_iter().flat_map(to_chars))
+    }
+
+    /// Any run of bytes within a path component. UTF-8 continuation bytes
+    /// never equal `/`, so this needs no char-level lowering.
+    fn star(&self, at_start: bool) -> Regex {
+        let rest = Regex::byte_class([(0, b'/' - 1), (b'/' + 1, 255)]).star();
+        if at_start && self.options.literal_leading_dot {
+            Regex::byte_class([(0, b'.' - 1), (b'/' + 1, 255)])
+                .then(rest)
+                .optional()
+        } else {
+            rest
+        }
+    }
+
+    /// A single path component, as matched by `**`.
+    fn component(&self) -> Regex {
+        if self.options.literal_leading_dot {
+            self.star(true)
+        } else {
+            self.star(false)
+        }
+    }
+
+    /// A trailing `**`: any number of components.
+    fn any_path(&self) -> Regex {
+        if self.options.literal_leading_dot {
+            let component = self.component();
+            component
+                .clone()
+                .then(Regex::from('/').then(component).star())
+        } else {
+            Regex::Dot.star()
+        }
+    }
+}
+
+fn unclosed_class() -> Error {
+    Error::Syntax("Unclosed '[' in glob".to_string())
+}
+
+fn fold_ascii(ranges: &[(u32, u32)]) -> Vec<(u32, u32)> {
+    let mut folded = ranges.to_vec();
+    for &(lo, hi) in ranges {
+        for (from, to) in [('a', 'A'), ('A', 'a')] {
+            let (from, to) = (from as u32, to as u32);
+            let start = lo.max(from);
+            let end = hi.min(from + 25);
+            if start <= end {
+                folded.push((start - from + to, end - from + to));
+            }
+        }
+    }
+    folded
+}
+
+fn complement(ranges: &[(u32, u32)]) -> Vec<(u32, u32)> {
+    let mut sorted = ranges.to_vec();
+    sorted.sort_unstable();
+
+    let mut out = Vec::new();
+    let mut next = 0u32;
+    for (lo, hi) in sorted {
+        if lo > next {
+            out.push((next, lo - 1));
+        }
+        next = next.max(hi + 1);
+    }
+    if next <= char::MAX as u32 {
+        out.push((next, char::MAX as u32));
+    }
+    out
+}
+
+fn subtract(ranges: &[(u32, u32)], c: char) -> Vec<(u32, u32)> {
+    let c = c as u32;
+    let mut out = Vec::new();
+    for &(lo, hi) in ranges {
+        if c < lo || c > hi {
+            out.push((lo, hi));
+            continue;
+        }
+        if lo < c {
+            out.push((lo, c - 1));
+        }
+        if c < hi {
+            out.push((c + 1, hi));
+        }
+    }
+    out
+}
+
+/// Converts a range of code points to char ranges, skipping surrogates.
+fn to_chars((lo, hi): (u32, u32)) -> Vec<std::ops::RangeInclusive<char>> {
+    [(lo, hi.min(0xD7FF)), (lo.max(0xE000), hi)]
+        .into_iter()
+        .filter(|(lo, hi)| lo <= hi)
+        .filter_map(|(lo, hi)| Some(char::from_u32(lo)?..=char::from_u32(hi)?))
+        .collect()
+}
@@ -1,4 +1,13 @@
-mod parser;
+mod utf8;
 pub mod ast;
+pub mod builder;
 pub mod engine;
+pub mod error;
+pub mod glob;
+pub mod like;
 pub mod matcher;
+pub mod overlapping;
+pub mod parser;
+pub mod reader;
+pub mod set;
+pub mod stream;
@@ -0,0 +1,96 @@
+use crate::{
+    ast::Regex,
+    error::{Error, Limits},
+    matcher::AdamRegex,
+};
+
+#[derive(Debug, Clone)]
+pub struct LikeOptions {
+    /// The character given in an `ESCAPE` clause, or `None` to treat every
+    /// character except `%` and `_` literally. Defaults to `\`.
+    pub escape: Option<char>,
+    /// Compare case-insensitively, as `ILIKE` does.
+    pub case_insensitive: bool,
+}
+
+impl Default for LikeOptions {
+    fn default() -> Self {
+        Self {
+            escape: Some('\\'),
+            case_insensitive: false,
+        }
+    }
+}
+
+/// Translates a SQL `LIKE` pattern into a regex that matches whole strings.
+///
+/// `%` matches any sequence of characters and `_` exactly one character.
+/// The escape character makes the following `%`, `_` or escape character
+/// literal; escaping anything else is an error, as is a trailing escape.
+pub fn parse(pattern: &str) -> Result<Regex, Error> {
+    parse_with(pattern, &LikeOptions::default())
+}
+
+pub fn parse_with(pattern: &str, options: &LikeOptions) -> Result<Regex, Error> {
+    if matches!(options.escape, Some('%') | Some('_')) {
+        return Err(Error::Syntax(
+            "LIKE escape character cannot be '%' or '_'".to_string(),
+        ));
+    }
+
+    let literal = |c: char| {
+        if options.case_insensitive {
+            Regex::literal_ignore_case(c.encode_utf8(&mut [0; 4]))
+        } else {
+            Regex::from(c)
+        }
+    };
+
+    let mut items = Vec::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        let item = match c {
+            c if Some(c) == options.escape => match chars.next() {
+                Some(escaped)
+                    if escaped == '%' || escaped == '_' || Some(escaped) == options.escape =>
+                {
+                    literal(escaped)
+                }
+                Some(escaped) => {
+                    return Err(Error::Syntax(format!(
+                        "Invalid escape sequence '{}{}' in LIKE pattern",
+                        c, escaped
+                    )))
+                }
+                None => {
+                    return Err(Error::Syntax(
+                        "LIKE pattern must not end with the escape character".to_string(),
+                    ))
+                }
+            },
+            '%' => {
+                // Runs of % are equivalent to a single one.
+                if matches!(items.last(), Some(Regex::Star(inner)) if **inner == Regex::Dot) {
+                    continue;
+                }
+                Regex::Dot.star()
+            }
+            '_' => Regex::any_char(),
+            c => literal(c),
+        };
+        items.push(item);
+    }
+
+    Ok(items
+        .into_iter()
+        .reduce(Regex::then)
+        .unwrap_or(Regex::Empty))
+}
+
+/// Compiles a `LIKE` pattern straight to a matcher, so that
+/// `AdamRegex::matches` evaluates the predicate. The pattern comes from
+/// outside, so it is built within `limits` like any other.
+pub fn compile(pattern: &str, options: &LikeOptions, limits: &Limits) -> Result<AdamRegex, Error> {
+    let ast = parse_with(pattern, options)?;
+    AdamRegex::builder().limits(limits.clone()).build_ast(&ast)
+}
@@ -1,26 +1,465 @@
+use std::io::Read;
+use std::ops::Range;
+use std::sync::{Arc, OnceLock};
+
 use crate::{
     ast::Regex,
-    engine::{compiler, dfa::DFA},
-    parser::parser,
+    engine::{
+        bitparallel::BitParallel,
+        compiler::{self, Construction},
+        dfa::{Priority, DFA},
+        glushkov::Glushkov,
+        lazy::{Deferred, Direction, LazyDFA},
+        nfa::NFA,
+        pike::Program,
+        prefilter::{InnerLiteral, Prefilter},
+    },
+    error::{Error, Limits},
+    overlapping::OverlappingMatches,
+    parser::{parser, Dialect},
+    reader::ReaderMatches,
+    set::RegexSet,
+    stream::Matcher,
 };
 
 pub struct AdamRegex {
-    dfa: DFA,
+    pub(crate) engine: Engine,
+    search: Search,
+    /// The pattern, for what is built on first use.
+    pattern: Arc<[Regex]>,
+    /// The leftmost-longest search for where a match ends, and the reversed
+    /// pattern for where it starts.
+    forward: Deferred,
+    reverse: Deferred,
+    /// The pattern with any prefix allowed, for `rfind`.
+    unanchored: Deferred,
+    /// NFA threads for patterns with anchors, for searches without DFAs,
+    /// and for overlapping and stream search. Built on first use, unless
+    /// the pattern has anchors.
+    program: OnceLock<Program>,
+}
+
+/// How `find` narrows down where matches can be.
+enum Search {
+    /// Runs the forward and reverse DFAs from the start of the search.
+    Scan,
+    /// Runs them from the first position where a prefix literal occurs.
+    Prefix(Prefilter),
+    /// Looks for a literal inside the pattern and works outwards.
+    Inner(Box<InnerLiteral>),
+    /// Runs the threads of `AdamRegex::program`, which hold branches to
+    /// their ERE anchors.
+    Threads,
+}
+
+impl Search {
+    fn new(ast: &Regex, limits: &Limits) -> Self {
+        if let Some(prefilter) = Prefilter::new(ast) {
+            Search::Prefix(prefilter)
+        } else if let Some(inner) = InnerLiteral::new(ast, limits) {
+            Search::Inner(Box::new(inner))
+        } else {
+            Search::Scan
+        }
+    }
 }
 
+pub(crate) enum Engine {
+    Dfa(DFA),
+    Lazy(LazyDFA),
+    BitParallel(BitParallel),
+    Nfa(NFA),
+}
+
+impl Engine {
+    fn longest_match(&self, input: &[u8]) -> Option<usize> {
+        match self {
+            Engine::Dfa(dfa) => dfa.longest_match(input),
+            Engine::Lazy(lazy) => lazy.longest_match(input),
+            Engine::BitParallel(bits) => bits.longest_match(input),
+            Engine::Nfa(nfa) => nfa.longest_match(input),
+        }
+    }
+
+    /// Length of some matching prefix of `input`, stopping as early as the
+    /// engine can.
+    fn shortest_match(&self, input: &[u8]) -> Option<usize> {
+        match self {
+            Engine::Dfa(dfa) => dfa.shortest_match(input),
+            Engine::Lazy(lazy) => lazy.shortest_match(input),
+            Engine::BitParallel(bits) => bits.shortest_match(input),
+            Engine::Nfa(nfa) => nfa.shortest_match(input),
+        }
+    }
+}
+
+/// A pattern small enough to run bit-parallel only gets a DFA if the DFA
+/// may need at most this many states; beyond that, building it could cost
+/// more than a one-off match saves.
+const SMALL_DFA_STATES: usize = 256;
+
 impl AdamRegex {
-    pub fn from_str(input: &str) -> Result<Self, String> {
-        let ast = parser::parse(input);
-        let dfa = compiler::compile(&ast, true);
-        Ok(Self { dfa })
+    fn new(engine: Engine, ast: &Regex, limits: &Limits) -> Self {
+        let pattern: Arc<[Regex]> = Arc::from([ast.clone()]);
+        let deferred = |direction| Deferred::new(pattern.clone(), direction, limits.max_dfa_states);
+        Self {
+            engine,
+            search: Search::new(ast, limits),
+            forward: deferred(Direction::Leftmost),
+            reverse: deferred(Direction::Reverse),
+            unanchored: deferred(Direction::Unanchored),
+            program: OnceLock::new(),
+            pattern,
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(input: &str) -> Result<Self, Error> {
+        RegexBuilder::new().build(input)
     }
 
     pub fn from_ast(ast: &Regex) -> Self {
         let dfa = compiler::compile(ast, true);
-        Self { dfa }
+        Self::new(Engine::Dfa(dfa), ast, &Limits::default())
+    }
+
+    pub fn builder() -> RegexBuilder {
+        RegexBuilder::new()
     }
 
     pub fn matches(&self, input: &str) -> bool {
-        self.dfa.matches(input)
+        match &self.engine {
+            Engine::Dfa(dfa) => dfa.matches(input),
+            Engine::Lazy(lazy) => lazy.matches(input),
+            Engine::BitParallel(bits) => bits.matches(input),
+            Engine::Nfa(nfa) => nfa.matches(input),
+        }
+    }
+
+    /// A matcher that takes the input in pieces; see `Matcher`.
+    pub fn matcher(&self) -> Matcher<'_> {
+        Matcher::new(&self.engine)
+    }
+
+    /// Whether any substring of `haystack` matches. Unlike `find`, this
+    /// stops at the first accepting state rather than extending the match:
+    /// the unanchored DFA reads up to the first end of a match in one pass,
+    /// or if it would be too big, NFA threads do.
+    pub fn is_match(&self, haystack: &str) -> bool {
+        let bytes = haystack.as_bytes();
+        let at = match &self.search {
+            Search::Inner(inner) => {
+                let shortest = |input: &[u8]| self.engine.shortest_match(input);
+                return inner.find(bytes, 0, shortest).is_some();
+            }
+            Search::Threads => return self.program().is_match(bytes),
+            Search::Prefix(prefilter) => match prefilter.find(bytes, 0) {
+                Some(at) => at,
+                None => return false,
+            },
+            Search::Scan => 0,
+        };
+        match self.unanchored.dfa() {
+            Some(dfa) => dfa.shortest_match(&bytes[at..]).is_some(),
+            None => self.program().is_match(&bytes[at..]),
+        }
+    }
+
+    /// The leftmost match in `haystack`, extended as far as it goes.
+    pub fn find<'h>(&self, haystack: &'h str) -> Option<Match<'h>> {
+        self.find_at(haystack, 0)
+    }
+
+    /// The match that ends last in `haystack`, extended as far left as it
+    /// goes: `find` with the haystack read from the end. An unanchored DFA
+    /// finds the last end in one pass and a reverse DFA the start in
+    /// another, both built on the first call. If either would be too big,
+    /// or the pattern has anchors, NFA threads find both in one pass
+    /// instead.
+    pub fn rfind<'h>(&self, haystack: &'h str) -> Option<Match<'h>> {
+        let bytes = haystack.as_bytes();
+        let dfas = match self.search {
+            Search::Threads => (None, None),
+            _ => (self.unanchored.dfa(), self.reverse.dfa()),
+        };
+        let (start, end) = match dfas {
+            (Some(forward), Some(reverse)) => {
+                let end = forward.longest_match(bytes)?;
+                let len = reverse
+                    .longest_match_rev(&bytes[..end])
+                    .expect("a match ends here");
+                (end - len, end)
+            }
+            _ => self.program().rfind(bytes)?,
+        };
+        Some(Match {
+            haystack,
+            pattern: 0,
+            start,
+            end,
+        })
+    }
+
+    /// Every position where a match ends, each with the longest match
+    /// ending there, so matches may overlap: `aba` in `ababa` is found at
+    /// `0..3` and `2..5`. Takes time linear in the haystack, however many
+    /// matches there are and however long, by running NFA threads rather
+    /// than a DFA; see `OverlappingMatches`.
+    pub fn find_overlapping_iter<'r, 'h>(
+        &'r self,
+        haystack: &'h str,
+    ) -> OverlappingMatches<'r, 'h> {
+        OverlappingMatches::new(self.program(), haystack)
+    }
+
+    /// Successive non-overlapping leftmost-longest matches. After an empty
+    /// match the search resumes one byte further on.
+    pub fn find_iter<'r, 'h>(&'r self, haystack: &'h str) -> FindMatches<'r, 'h> {
+        FindMatches {
+            regex: self,
+            haystack,
+            at: 0,
+        }
+    }
+
+    /// `find_iter` over a stream, with offsets from its start. The stream
+    /// is read through a bounded buffer rather than all at once; see
+    /// `ReaderMatches`.
+    pub fn find_iter_reader<R: Read>(&self, reader: R) -> ReaderMatches<'_, R> {
+        ReaderMatches::new(self, reader)
+    }
+
+    pub(crate) fn program(&self) -> &Program {
+        self.program
+            .get_or_init(|| Program::single(&self.pattern[0]))
+    }
+
+    /// The prefix literal search `find` jumps with, if any.
+    pub(crate) fn prefilter(&self) -> Option<&Prefilter> {
+        match &self.search {
+            Search::Prefix(prefilter) => Some(prefilter),
+            _ => None,
+        }
+    }
+
+    /// The forward DFA of a leftmost-longest search and the reverse DFA,
+    /// unless either would be too big or the pattern has anchors.
+    pub(crate) fn dfas(&self) -> Option<(&DFA, &DFA)> {
+        match self.search {
+            Search::Threads => None,
+            _ => Some((self.forward.dfa()?, self.reverse.dfa()?)),
+        }
+    }
+
+    /// The leftmost-longest match starting at or after `at`. The forward
+    /// DFA finds where it ends in one pass, and the reverse DFA where it
+    /// starts in another over the same bytes, both built on the first
+    /// call. If either would be too big, NFA threads find both in one
+    /// pass instead.
+    fn find_at<'h>(&self, haystack: &'h str, mut at: usize) -> Option<Match<'h>> {
+        let bytes = haystack.as_bytes();
+        let (start, end) = match &self.search {
+            Search::Inner(inner) => {
+                let longest = |input: &[u8]| self.engine.longest_match(input);
+                inner.find(bytes, at, longest)?
+            }
+            Search::Threads => self.program().find_at(bytes, at)?,
+            Search::Scan | Search::Prefix(_) => {
+                if let Search::Prefix(prefilter) = &self.search {
+                    at = prefilter.find(bytes, at)?;
+                }
+                match self.dfas() {
+                    Some((forward, reverse)) => {
+                        let end = at + forward.longest_match(bytes.get(at..)?)?;
+                        let len = reverse
+                            .longest_match_rev(&bytes[at..end])
+                            .expect("a match ends here");
+                        (end - len, end)
+                    }
+                    None => self.program().find_at(bytes, at)?,
+                }
+            }
+        };
+        Some(Match {
+            haystack,
+            pattern: 0,
+            start,
+            end,
+        })
+    }
+}
+
+/// A match found by searching, as a byte range of the haystack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match<'h> {
+    pub(crate) haystack: &'h str,
+    pub(crate) pattern: usize,
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+}
+
+impl<'h> Match<'h> {
+    /// The pattern that matched: its index in a `RegexSet`, or 0 for an
+    /// `AdamRegex`.
+    pub fn pattern_id(&self) -> usize {
+        self.pattern
+    }
+
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// The matched bytes. Patterns match bytes, so a match need not fall
+    /// on char boundaries.
+    pub fn as_bytes(&self) -> &'h [u8] {
+        &self.haystack.as_bytes()[self.range()]
+    }
+}
+
+/// Iterator returned by `AdamRegex::find_iter`.
+pub struct FindMatches<'r, 'h> {
+    regex: &'r AdamRegex,
+    haystack: &'h str,
+    at: usize,
+}
+
+impl<'h> Iterator for FindMatches<'_, 'h> {
+    type Item = Match<'h>;
+
+    fn next(&mut self) -> Option<Match<'h>> {
+        let m = self.regex.find_at(self.haystack, self.at)?;
+        self.at = if m.is_empty() { m.end + 1 } else { m.end };
+        Some(m)
+    }
+}
+
+/// Configures how patterns are compiled into an `AdamRegex`.
+#[derive(Debug, Clone, Default)]
+pub struct RegexBuilder {
+    limits: Limits,
+    dialect: Dialect,
+    lazy: bool,
+    construction: Construction,
+    priority: Priority,
+}
+
+impl RegexBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Selects the pattern syntax used by `build`. Matching is leftmost-longest
+    /// in every dialect, as POSIX requires.
+    pub fn dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Determinizes on demand while matching rather than up front. This
+    /// suits patterns whose full DFA is huge, such as `(a|b)*a(a|b){20}`;
+    /// `Limits::max_dfa_states` then bounds the state cache. A `Matcher`
+    /// keeps a cache of its own with the same bound, so that between pieces
+    /// of input it carries only a state ID into it.
+    pub fn lazy(mut self, lazy: bool) -> Self {
+        self.lazy = lazy;
+        self
+    }
+
+    /// Selects how the NFA is built; see `Construction`.
+    pub fn construction(mut self, construction: Construction) -> Self {
+        self.construction = construction;
+        self
+    }
+
+    /// Selects which pattern of a `RegexSet` wins when several match at
+    /// the same start; see `Priority`. Single patterns ignore it.
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn build(&self, input: &str) -> Result<AdamRegex, Error> {
+        let (ast, branches) = parser::try_parse_anchored(input, self.dialect, &self.limits)?;
+        let mut regex = self.build_ast(&ast)?;
+        if !branches.is_empty() {
+            regex.search = Search::Threads;
+            regex.program = OnceLock::from(Program::anchored(&branches));
+        }
+        Ok(regex)
+    }
+
+    /// Builds a `RegexSet`. The set is always a single eager DFA, so the
+    /// `lazy` and `construction` settings do not apply, and ERE anchors
+    /// are rejected.
+    pub fn build_set<I>(&self, patterns: I) -> Result<RegexSet, Error>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let asts = patterns
+            .into_iter()
+            .map(|p| {
+                let (ast, branches) =
+                    parser::try_parse_anchored(p.as_ref(), self.dialect, &self.limits)?;
+                if !branches.is_empty() {
+                    return Err(Error::Syntax(
+                        "Anchors are not supported in a RegexSet".to_string(),
+                    ));
+                }
+                Ok(ast)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut set = RegexSet::from_asts(&asts, &self.limits)?;
+        set.priority = self.priority;
+        Ok(set)
+    }
+
+    /// Picks the engine from the size of the pattern rather than by
+    /// building the DFA to see: patterns whose DFA may be large run
+    /// bit-parallel if they are small enough, and otherwise get a DFA
+    /// within `Limits::max_dfa_states` or failing that, NFA simulation.
+    pub fn build_ast(&self, ast: &Regex) -> Result<AdamRegex, Error> {
+        let engine = if self.lazy {
+            Engine::Lazy(compiler::compile_lazy(
+                ast,
+                self.construction,
+                &self.limits,
+            )?)
+        } else {
+            let nfa = compiler::compile_nfa(ast, self.construction, &self.limits)?;
+            let glushkov = Glushkov::new(ast);
+            let small_dfa = self.limits.max_dfa_states.min(SMALL_DFA_STATES);
+            match BitParallel::new(&glushkov) {
+                Some(bits) if glushkov.dfa_states_bound() > small_dfa => Engine::BitParallel(bits),
+                // Past the budget, simulate rather than fail
+                _ => match nfa.try_to_dfa(self.limits.max_dfa_states) {
+                    Ok(dfa) => Engine::Dfa(dfa.minimize()),
+                    Err(Error::TooManyDfaStates { .. }) => Engine::Nfa(nfa),
+                    Err(e) => return Err(e),
+                },
+            }
+        };
+        Ok(AdamRegex::new(engine, ast, &self.limits))
     }
 }
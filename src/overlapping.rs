@@ -0,0 +1,78 @@
+use crate::{
+    engine::pike::{Program, Threads},
+    matcher::Match,
+};
+
+/// Iterator returned by `AdamRegex::find_overlapping_iter` and
+/// `RegexSet::find_overlapping_iter`.
+///
+/// The haystack is read once, with a thread of the NFA starting at every
+/// position. Wherever some pattern's accept state has a thread, the
+/// leftmost of them gives the start of its longest match ending there.
+/// Matches come in order of their end, then of pattern ID.
+///
+/// This runs NFA threads rather than a DFA. A DFA state only says which
+/// patterns end at a position, not where their matches start, and finding
+/// each start with the reverse DFA reads back over the whole match, which
+/// is quadratic when overlapping matches are long. The threads carry every
+/// start along instead, in O(n·m) time for n bytes and m NFA states.
+pub struct OverlappingMatches<'r, 'h> {
+    program: &'r Program,
+    haystack: &'h str,
+    threads: Threads,
+    /// Bytes read so far.
+    at: usize,
+    /// Patterns with a match ending at `at` still to report, with its
+    /// start, last first.
+    pending: Vec<(usize, usize)>,
+}
+
+impl<'r, 'h> OverlappingMatches<'r, 'h> {
+    pub(crate) fn new(program: &'r Program, haystack: &'h str) -> Self {
+        let mut matches = Self {
+            program,
+            haystack,
+            threads: program.threads(),
+            at: 0,
+            pending: Vec::new(),
+        };
+        program.enter(&mut matches.threads, 0);
+        matches.queue();
+        matches
+    }
+
+    /// Queues the patterns whose accept state has a thread, with the
+    /// leftmost start among their branches.
+    fn queue(&mut self) {
+        let at_end = self.at == self.haystack.len();
+        for (pattern, start) in self.program.matches(&self.threads, at_end) {
+            match self.pending.last_mut() {
+                Some(last) if last.0 == pattern => last.1 = last.1.min(start),
+                _ => self.pending.push((pattern, start)),
+            }
+        }
+        self.pending.reverse();
+    }
+}
+
+impl<'h> Iterator for OverlappingMatches<'_, 'h> {
+    type Item = Match<'h>;
+
+    fn next(&mut self) -> Option<Match<'h>> {
+        loop {
+            if let Some((pattern, start)) = self.pending.pop() {
+                return Some(Match {
+                    haystack: self.haystack,
+                    pattern,
+                    start,
+                    end: self.at,
+                });
+            }
+            let &byte = self.haystack.as_bytes().get(self.at)?;
+            self.program.step(&mut self.threads, byte);
+            self.at += 1;
+            self.program.enter(&mut self.threads, self.at);
+            self.queue();
+        }
+    }
+}
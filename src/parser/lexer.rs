@@ -1,5 +1,18 @@
 use std::str::Chars;
 
+/// The pattern syntax accepted by the lexer.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Dialect {
+    /// Alphanumerics and the operators `* + ? . | ( )`.
+    #[default]
+    Default,
+    /// POSIX Extended Regular Expressions, as used by `grep -E`: bracket
+    /// expressions, intervals, `\` quoting of special characters and no
+    /// Perl escapes such as `\d`.
+    Ere,
+}
+
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     Byte(u8),
@@ -11,32 +24,67 @@ pub enum Token {
     LParen,
     RParen,
     Alt,
+    Bracket {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+    Repeat(usize, Option<usize>),
+    Caret,
+    Dollar,
     EOF,
 }
 
 impl Token {
     pub fn is_atom_start(&self) -> bool {
-        matches!(self, Token::Byte(_) | Token::Dot | Token::LParen)
+        matches!(
+            self,
+            Token::Byte(_) | Token::Char(_) | Token::Dot | Token::LParen | Token::Bracket { .. }
+        )
     }
 }
 
+/// The largest bound allowed in an ERE interval, POSIX's `RE_DUP_MAX`.
+const DUP_MAX: usize = 255;
+
 pub struct Lexer<'a> {
     input: Chars<'a>,
+    dialect: Dialect,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Self {
+        Self::with_dialect(input, Dialect::Default)
+    }
+
+    pub fn with_dialect(input: &'a str, dialect: Dialect) -> Self {
         Self {
             input: input.chars(),
+            dialect,
         }
     }
 
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
     fn next_char(&mut self) -> Option<char> {
         self.input.next()
     }
 
+    fn peek_char(&self) -> Option<char> {
+        self.input.clone().next()
+    }
+
     pub fn next_token(&mut self) -> Token {
-        match self.next_char() {
+        self.try_next_token().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_next_token(&mut self) -> Result<Token, String> {
+        if self.dialect == Dialect::Ere {
+            return self.next_ere_token();
+        }
+
+        let token = match self.next_char() {
             Some(c) if c.is_ascii_alphanumeric() => Token::Byte(c as u8),
             Some(c) if c.is_alphanumeric() => Token::Char(c),
             Some('*') => Token::Star,
@@ -47,9 +95,173 @@ impl<'a> Lexer<'a> {
             Some('(') => Token::LParen,
             Some(')') => Token::RParen,
             None => Token::EOF,
-            Some(other) => panic!("Unknown character: {}", other),
+            Some(other) => return Err(format!("Unknown character: {}", other)),
+        };
+        Ok(token)
+    }
+
+    fn next_ere_token(&mut self) -> Result<Token, String> {
+        let token = match self.next_char() {
+            Some('*') => Token::Star,
+            Some('+') => Token::Plus,
+            Some('?') => Token::Question,
+            Some('.') => Token::Dot,
+            Some('|') => Token::Alt,
+            Some('(') => Token::LParen,
+            Some(')') => Token::RParen,
+            Some('^') => Token::Caret,
+            Some('$') => Token::Dollar,
+            Some('[') => self.lex_bracket()?,
+            Some('{') => self.lex_interval()?,
+            Some('\\') => match self.next_char() {
+                Some(c) if ".[]()*+?{}|^$\\".contains(c) => Token::Byte(c as u8),
+                Some(c) => return Err(format!("Unsupported escape in ERE: \\{}", c)),
+                None => return Err("Trailing '\\' in ERE".to_string()),
+            },
+            Some(c) if c.is_ascii() => Token::Byte(c as u8),
+            Some(c) => Token::Char(c),
+            None => Token::EOF,
+        };
+        Ok(token)
+    }
+
+    /// Lexes `{m}`, `{m,}` or `{m,n}` after the opening brace.
+    fn lex_interval(&mut self) -> Result<Token, String> {
+        let mut body = String::new();
+        loop {
+            match self.next_char() {
+                Some('}') => break,
+                Some(c) => body.push(c),
+                None => return Err("Unclosed '{' in ERE".to_string()),
+            }
+        }
+
+        let invalid = || format!("Invalid interval '{{{}}}' in ERE", body);
+        let bound = |s: &str| -> Result<usize, String> {
+            match s.parse::<usize>() {
+                Ok(n) if n <= DUP_MAX && !s.starts_with('+') => Ok(n),
+                _ => Err(invalid()),
+            }
+        };
+
+        let (min, max) = match body.split_once(',') {
+            None => {
+                let n = bound(&body)?;
+                (n, Some(n))
+            }
+            Some((min, "")) => (bound(min)?, None),
+            Some((min, max)) => (bound(min)?, Some(bound(max)?)),
+        };
+        if max.is_some_and(|max| max < min) {
+            return Err(invalid());
+        }
+        Ok(Token::Repeat(min, max))
+    }
+
+    /// Lexes a bracket expression after the opening `[`. A `]` straight
+    /// after `[` or `[^` is literal, as is a `-` at either end, and `\`
+    /// has no special meaning.
+    fn lex_bracket(&mut self) -> Result<Token, String> {
+        let unclosed = || "Unclosed '[' in ERE".to_string();
+
+        let negated = self.peek_char() == Some('^');
+        if negated {
+            self.next_char();
+        }
+
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let c = self.next_char().ok_or_else(unclosed)?;
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+
+            let lo = if c == '[' {
+                match self.peek_char() {
+                    Some(':') => {
+                        self.next_char();
+                        let name = self.lex_bracket_term(':')?;
+                        ranges.extend(named_class(&name)?);
+                        continue;
+                    }
+                    Some(delim @ ('=' | '.')) => {
+                        self.next_char();
+                        single_char(&self.lex_bracket_term(delim)?)?
+                    }
+                    _ => c,
+                }
+            } else {
+                c
+            };
+
+            let mut lookahead = self.input.clone();
+            let hi = match (lookahead.next(), lookahead.next()) {
+                (Some('-'), Some(hi)) if hi != ']' => {
+                    self.next_char();
+                    self.next_char();
+                    if hi == '[' && self.peek_char() == Some('.') {
+                        self.next_char();
+                        single_char(&self.lex_bracket_term('.')?)?
+                    } else {
+                        hi
+                    }
+                }
+                _ => lo,
+            };
+            if lo > hi {
+                return Err(format!("Invalid range '{}-{}' in ERE", lo, hi));
+            }
+            ranges.push((lo, hi));
         }
+
+        Ok(Token::Bracket { ranges, negated })
     }
+
+    /// Reads the name of a `[:name:]`, `[=c=]` or `[.c.]` term, up to and
+    /// including its closing `delim]`.
+    fn lex_bracket_term(&mut self, delim: char) -> Result<String, String> {
+        let mut name = String::new();
+        loop {
+            match self.next_char() {
+                Some(c) if c == delim && self.peek_char() == Some(']') => {
+                    self.next_char();
+                    return Ok(name);
+                }
+                Some(c) => name.push(c),
+                None => return Err("Unclosed '[' in ERE".to_string()),
+            }
+        }
+    }
+}
+
+fn single_char(term: &str) -> Result<char, String> {
+    let mut chars = term.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(format!("Unsupported collating element '{}' in ERE", term)),
+    }
+}
+
+/// The POSIX character classes, in the C locale.
+fn named_class(name: &str) -> Result<Vec<(char, char)>, String> {
+    let ranges = match name {
+        "alpha" => vec![('a', 'z'), ('A', 'Z')],
+        "digit" => vec![('0', '9')],
+        "alnum" => vec![('0', '9'), ('a', 'z'), ('A', 'Z')],
+        "upper" => vec![('A', 'Z')],
+        "lower" => vec![('a', 'z')],
+        "space" => vec![(' ', ' '), ('\t', '\r')],
+        "blank" => vec![(' ', ' '), ('\t', '\t')],
+        "punct" => vec![('!', '/'), (':', '@'), ('[', '`'), ('{', '~')],
+        "print" => vec![(' ', '~')],
+        "graph" => vec![('!', '~')],
+        "cntrl" => vec![('\0', '\x1F'), ('\x7F', '\x7F')],
+        "xdigit" => vec![('0', '9'), ('a', 'f'), ('A', 'F')],
+        _ => return Err(format!("Unknown character class '[:{}:]' in ERE", name)),
+    };
+    Ok(ranges)
 }
 
 #[cfg(test)]
@@ -173,4 +385,85 @@ mod lexer_tests {
             ]
         );
     }
+
+    fn lex_ere(input: &str) -> Result<Vec<Token>, String> {
+        let mut lexer = Lexer::with_dialect(input, Dialect::Ere);
+        let mut tokens = Vec::new();
+        loop {
+            let tok = lexer.try_next_token()?;
+            tokens.push(tok.clone());
+            if tok == Token::EOF {
+                break;
+            }
+        }
+        Ok(tokens)
+    }
+
+    #[test]
+    fn ere_literals_and_anchors() {
+        assert_eq!(
+            lex_ere("^a-b\\.$").unwrap(),
+            vec![
+                Token::Caret,
+                Token::Byte(b'a'),
+                Token::Byte(b'-'),
+                Token::Byte(b'b'),
+                Token::Byte(b'.'),
+                Token::Dollar,
+                Token::EOF
+            ]
+        );
+    }
+
+    #[test]
+    fn ere_intervals() {
+        assert_eq!(
+            lex_ere("{3}{2,}{0,5}").unwrap(),
+            vec![
+                Token::Repeat(3, Some(3)),
+                Token::Repeat(2, None),
+                Token::Repeat(0, Some(5)),
+                Token::EOF
+            ]
+        );
+        assert!(lex_ere("{5,2}").is_err());
+        assert!(lex_ere("{,2}").is_err());
+        assert!(lex_ere("{256}").is_err());
+        assert!(lex_ere("{1").is_err());
+    }
+
+    #[test]
+    fn ere_bracket_expressions() {
+        assert_eq!(
+            lex_ere("[]a-c-]").unwrap()[0],
+            Token::Bracket {
+                ranges: vec![(']', ']'), ('a', 'c'), ('-', '-')],
+                negated: false
+            }
+        );
+        assert_eq!(
+            lex_ere("[^\\]").unwrap()[0],
+            Token::Bracket {
+                ranges: vec![('\\', '\\')],
+                negated: true
+            }
+        );
+        assert_eq!(
+            lex_ere("[[:digit:][=x=][.-.]]").unwrap()[0],
+            Token::Bracket {
+                ranges: vec![('0', '9'), ('x', 'x'), ('-', '-')],
+                negated: false
+            }
+        );
+        assert!(lex_ere("[abc").is_err());
+        assert!(lex_ere("[[:word:]]").is_err());
+        assert!(lex_ere("[z-a]").is_err());
+    }
+
+    #[test]
+    fn ere_rejects_perl_escapes() {
+        assert!(lex_ere("\\d").is_err());
+        assert!(lex_ere("\\w").is_err());
+        assert!(lex_ere("a\\").is_err());
+    }
 }
@@ -1,2 +1,5 @@
-mod lexer;
+pub mod lexer;
+#[allow(clippy::module_inception)]
 pub mod parser;
+
+pub use lexer::Dialect;
@@ -1,103 +1,293 @@
 use crate::ast::Regex;
-use crate::parser::lexer::{Lexer, Token};
+use crate::error::{Error, Limits};
+use crate::parser::lexer::{Dialect, Lexer, Token};
 
 pub struct Parser<'a> {
     lexer: Lexer<'a>,
     current: Token,
+    limits: Limits,
+    nodes: usize,
+    groups: usize,
+    /// The anchors of each top-level branch parsed so far.
+    anchors: Vec<Anchors>,
+}
+
+/// Which ends of the haystack an ERE `^` and `$` hold a top-level branch
+/// to. Whole-input matching ignores them; searching does not.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Anchors {
+    pub(crate) start: bool,
+    pub(crate) end: bool,
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(mut lexer: Lexer<'a>) -> Self {
-        let current = lexer.next_token();
-        Self { lexer, current }
+    pub fn new(lexer: Lexer<'a>) -> Self {
+        Self::with_limits(lexer, Limits::default())
+    }
+
+    pub fn with_limits(lexer: Lexer<'a>, limits: Limits) -> Self {
+        Self {
+            lexer,
+            current: Token::EOF,
+            limits,
+            nodes: 0,
+            groups: 0,
+            anchors: Vec::new(),
+        }
+    }
+
+    fn advance(&mut self) -> Result<(), Error> {
+        self.current = self.lexer.try_next_token().map_err(Error::Syntax)?;
+        Ok(())
+    }
+
+    /// Accounts for `count` new AST nodes.
+    fn charge(&mut self, count: usize) -> Result<(), Error> {
+        self.nodes = self.nodes.saturating_add(count);
+        if self.nodes > self.limits.max_ast_nodes {
+            return Err(Error::TooManyAstNodes {
+                limit: self.limits.max_ast_nodes,
+            });
+        }
+        Ok(())
     }
 
-    fn advance(&mut self) {
-        self.current = self.lexer.next_token();
+    /// Accounts for a new AST node whose deepest child is at `depth`, and
+    /// returns the depth of the new node. A run of concatenations or
+    /// alternations counts as a single level, since it is not nested.
+    fn node(&mut self, depth: usize) -> Result<usize, Error> {
+        self.charge(1)?;
+        if depth + 1 > self.limits.max_depth {
+            return Err(Error::NestingTooDeep {
+                limit: self.limits.max_depth,
+            });
+        }
+        Ok(depth + 1)
     }
 
-    pub fn parse(&mut self) -> Regex {
-        self.parse_alt()
+    /// Accounts for an atom that lowers to more than one node, such as a
+    /// UTF-8 encoded char or a bracket expression.
+    fn atom(&mut self, expr: Regex) -> Result<(Regex, usize), Error> {
+        self.charge(expr.node_count() - 1)?;
+        Ok((expr, self.node(0)?))
+    }
+
+    pub fn parse(&mut self) -> Result<Regex, Error> {
+        self.advance()?;
+        let (expr, _) = self.parse_alt()?;
+        match self.current {
+            Token::EOF => Ok(expr),
+            _ => Err(Error::Syntax(format!(
+                "Unexpected token: {:?}",
+                self.current
+            ))),
+        }
     }
 
-    pub fn parse_alt(&mut self) -> Regex {
-        let mut expr = self.parse_concat();
+    pub fn parse_alt(&mut self) -> Result<(Regex, usize), Error> {
+        let (mut expr, mut depth) = self.parse_concat()?;
+        let mut deepest = depth;
 
         while self.current == Token::Alt {
-            self.advance();
-            let right = self.parse_concat();
+            self.advance()?;
+            let (right, right_depth) = self.parse_concat()?;
+            deepest = deepest.max(right_depth);
+            depth = self.node(deepest)?;
             expr = Regex::Alt(Box::new(expr), Box::new(right));
         }
 
-        expr
+        Ok((expr, depth))
     }
 
-    fn parse_concat(&mut self) -> Regex {
-        let mut expr = self.parse_postfix();
+    fn parse_concat(&mut self) -> Result<(Regex, usize), Error> {
+        // ERE anchors are only accepted where they hold the whole branch to
+        // an end, and are kept aside from the AST.
+        let top_level = self.groups == 0;
+        let anchored = top_level && self.current == Token::Caret;
+        if anchored {
+            self.advance()?;
+        }
+
+        let empty = !self.current.is_atom_start()
+            && (anchored || top_level && self.current == Token::Dollar);
+        let (mut expr, mut depth) = if empty {
+            (Regex::Empty, self.node(0)?)
+        } else {
+            self.parse_postfix()?
+        };
+        let mut deepest = depth;
 
         while self.current.is_atom_start() {
-            let right = self.parse_postfix();
+            let (right, right_depth) = self.parse_postfix()?;
+            deepest = deepest.max(right_depth);
+            depth = self.node(deepest)?;
             expr = Regex::Concat(Box::new(expr), Box::new(right));
         }
 
-        expr
+        let ended = top_level && self.current == Token::Dollar;
+        if ended {
+            self.advance()?;
+            if !matches!(self.current, Token::Alt | Token::EOF) {
+                return Err(anchor_error());
+            }
+        }
+        if top_level {
+            self.anchors.push(Anchors {
+                start: anchored,
+                end: ended,
+            });
+        }
+
+        Ok((expr, depth))
     }
 
-    fn parse_postfix(&mut self) -> Regex {
-        let mut expr = self.parse_atom();
+    fn parse_postfix(&mut self) -> Result<(Regex, usize), Error> {
+        let (mut expr, mut depth) = self.parse_atom()?;
 
         loop {
             match self.current {
                 Token::Star => {
-                    self.advance();
+                    self.advance()?;
+                    depth = self.node(depth)?;
                     expr = Regex::Star(Box::new(expr));
                 }
                 Token::Plus => {
-                    self.advance();
+                    self.advance()?;
+                    depth = self.node(depth)?;
                     expr = Regex::Plus(Box::new(expr));
                 }
                 Token::Question => {
-                    self.advance();
+                    self.advance()?;
+                    depth = self.node(depth)?;
                     expr = Regex::Optional(Box::new(expr));
                 }
+                Token::Repeat(min, max) => {
+                    self.advance()?;
+                    // Each copy of the expression is a separate subtree.
+                    let copies = max.unwrap_or(min).max(1);
+                    self.charge(expr.node_count().saturating_add(1).saturating_mul(copies))?;
+                    depth = self.node(depth + 1)?;
+                    expr = match max {
+                        Some(max) => expr.repeat(min..=max),
+                        None => expr.repeat(min..),
+                    };
+                }
                 _ => break,
             }
         }
 
-        expr
+        Ok((expr, depth))
     }
 
-    fn parse_atom(&mut self) -> Regex {
+    fn parse_atom(&mut self) -> Result<(Regex, usize), Error> {
         match &self.current {
             Token::Byte(b) => {
                 let node = Regex::Byte(*b);
-                self.advance();
-                node
+                self.advance()?;
+                Ok((node, self.node(0)?))
+            }
+            Token::Char(c) => {
+                let node = Regex::from(*c);
+                self.advance()?;
+                self.atom(node)
+            }
+            Token::Bracket { ranges, negated } => {
+                let ranges = ranges.iter().map(|&(lo, hi)| lo..=hi);
+                let node = if *negated {
+                    Regex::class_except(ranges)
+                } else {
+                    Regex::class_of(ranges)
+                };
+                self.advance()?;
+                self.atom(node)
+            }
+            // POSIX `.` matches a character, as a negated bracket does
+            Token::Dot if self.lexer.dialect() == Dialect::Ere => {
+                self.advance()?;
+                self.atom(Regex::any_char())
             }
             Token::Dot => {
-                self.advance();
-                Regex::Dot
+                self.advance()?;
+                Ok((Regex::Dot, self.node(0)?))
             }
             Token::LParen => {
-                self.advance();
-                let expr = self.parse_alt();
+                // Groups add no AST node, but each one costs a level of
+                // recursion here.
+                self.groups += 1;
+                if self.groups > self.limits.max_depth {
+                    return Err(Error::NestingTooDeep {
+                        limit: self.limits.max_depth,
+                    });
+                }
+                self.advance()?;
+                let expr = self.parse_alt()?;
                 if self.current != Token::RParen {
-                    panic!("Expected ')'");
+                    return Err(Error::Syntax("Expected ')'".to_string()));
                 }
-                self.advance();
-                expr
+                self.groups -= 1;
+                self.advance()?;
+                Ok(expr)
             }
-            _ => panic!("Unexpected token: {:?}", self.current),
+            Token::Caret | Token::Dollar => Err(anchor_error()),
+            _ => Err(Error::Syntax(format!(
+                "Unexpected token: {:?}",
+                self.current
+            ))),
         }
     }
 }
 
+fn anchor_error() -> Error {
+    Error::Syntax("Anchors are only supported at the start or end of the pattern".to_string())
+}
+
 pub fn parse(input: &str) -> Regex {
-    let lexer = Lexer::new(input);
-    let mut parser = Parser::new(lexer);
+    try_parse(input, &Limits::default()).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_parse(input: &str, limits: &Limits) -> Result<Regex, Error> {
+    try_parse_dialect(input, Dialect::Default, limits)
+}
+
+pub fn try_parse_dialect(input: &str, dialect: Dialect, limits: &Limits) -> Result<Regex, Error> {
+    let lexer = Lexer::with_dialect(input, dialect);
+    let mut parser = Parser::with_limits(lexer, limits.clone());
     parser.parse()
 }
 
+/// Parses `input`, and if any top-level branch has an anchor, also
+/// returns every top-level branch with its anchors.
+pub(crate) fn try_parse_anchored(
+    input: &str,
+    dialect: Dialect,
+    limits: &Limits,
+) -> Result<(Regex, Vec<(Regex, Anchors)>), Error> {
+    let lexer = Lexer::with_dialect(input, dialect);
+    let mut parser = Parser::with_limits(lexer, limits.clone());
+    let ast = parser.parse()?;
+    if parser
+        .anchors
+        .iter()
+        .all(|&anchors| anchors == Anchors::default())
+    {
+        return Ok((ast, Vec::new()));
+    }
+    // The branches are the right children of the outermost `Alt` nodes,
+    // one fewer of them than there are branches
+    let mut branches = Vec::with_capacity(parser.anchors.len());
+    let mut node = &ast;
+    for &anchors in parser.anchors[1..].iter().rev() {
+        let Regex::Alt(left, right) = node else {
+            unreachable!("one Alt per top-level branch");
+        };
+        branches.push(((**right).clone(), anchors));
+        node = left;
+    }
+    branches.push((node.clone(), parser.anchors[0]));
+    branches.reverse();
+    Ok((ast, branches))
+}
+
 #[cfg(test)]
 mod parser_tests {
     use super::*;
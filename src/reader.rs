@@ -0,0 +1,292 @@
+use std::io::{self, Read};
+use std::ops::Range;
+
+use crate::engine::{
+    dfa::{StateKind, DFA},
+    pike::Threads,
+    prefilter::Prefilter,
+};
+use crate::matcher::AdamRegex;
+
+/// How many bytes a refill reads by default.
+const DEFAULT_CAPACITY: usize = 64 * 1024;
+
+/// How many bytes may be kept by default.
+const DEFAULT_BUFFER_LIMIT: usize = 64 * 1024 * 1024;
+
+/// A match found in a stream, as offsets from the start of the stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReaderMatch {
+    start: usize,
+    end: usize,
+    bytes: Option<Vec<u8>>,
+}
+
+impl ReaderMatch {
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// The matched bytes, if they were asked for with
+    /// `ReaderMatches::with_bytes`.
+    pub fn bytes(&self) -> Option<&[u8]> {
+        self.bytes.as_deref()
+    }
+}
+
+/// Iterator returned by `AdamRegex::find_iter_reader`.
+///
+/// Like `AdamRegex::find`, a search runs the forward DFA to where the match
+/// ends, keeping the bytes it reads, and the reverse DFA back over them to
+/// where it starts. Once it has kept `capacity` bytes, or if the pattern has
+/// no DFAs, NFA threads that each remember where their match started take
+/// over, so a match may span any number of reads and the search takes time
+/// linear in the stream. While they have found no match they keep no bytes
+/// at all. Once they have, the bytes after it are kept until it can grow no
+/// further, since the next search reads them again. A refill reads at most
+/// `capacity` bytes, and keeping more than `buffer_limit` bytes is an
+/// error.
+pub struct ReaderMatches<'r, R> {
+    regex: &'r AdamRegex,
+    reader: R,
+    /// Built on first use, since the DFAs usually do without.
+    threads: Option<Threads>,
+    /// The stream from offset `base` on, as far as it has been read.
+    buf: Vec<u8>,
+    base: usize,
+    /// Where the next match may start.
+    at: usize,
+    capacity: usize,
+    buffer_limit: usize,
+    bytes: bool,
+    eof: bool,
+    /// Set after an error, which ends the iteration.
+    failed: bool,
+}
+
+impl<'r, R: Read> ReaderMatches<'r, R> {
+    pub(crate) fn new(regex: &'r AdamRegex, reader: R) -> Self {
+        Self {
+            regex,
+            reader,
+            threads: None,
+            buf: Vec::new(),
+            base: 0,
+            at: 0,
+            capacity: DEFAULT_CAPACITY,
+            buffer_limit: DEFAULT_BUFFER_LIMIT,
+            bytes: false,
+            eof: false,
+            failed: false,
+        }
+    }
+
+    /// Also returns the matched bytes with each match. They are kept from
+    /// the start of every candidate match, so the buffer then grows with
+    /// the longest candidate rather than only with the bytes after a match.
+    pub fn with_bytes(mut self) -> Self {
+        self.bytes = true;
+        self
+    }
+
+    /// Reads at most `capacity` bytes at a time; 64 KiB by default.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        self.capacity = capacity;
+        self
+    }
+
+    /// Fails with `io::ErrorKind::OutOfMemory` rather than keep more than
+    /// `limit` bytes of the stream; 64 MiB by default.
+    pub fn buffer_limit(mut self, limit: usize) -> Self {
+        self.buffer_limit = limit;
+        self
+    }
+
+    /// Drops the bytes before `keep` and reads more of the stream. Returns
+    /// whether anything was read.
+    fn refill(&mut self, keep: usize) -> io::Result<bool> {
+        if self.eof {
+            return Ok(false);
+        }
+        self.buf.drain(..keep - self.base);
+        self.base = keep;
+        let len = self.buf.len();
+        if len > self.buffer_limit {
+            return Err(io::Error::new(
+                io::ErrorKind::OutOfMemory,
+                format!("match needs more than {} buffered bytes", self.buffer_limit),
+            ));
+        }
+        self.buf.resize(len + self.capacity, 0);
+        let read = loop {
+            match self.reader.read(&mut self.buf[len..]) {
+                Ok(n) => break n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.buf.truncate(len);
+                    return Err(e);
+                }
+            }
+        };
+        self.buf.truncate(len + read);
+        self.eof = read == 0;
+        Ok(read > 0)
+    }
+
+    /// Moves `pos` to where the prefilter finds the next candidate start,
+    /// reading as much as it takes. Returns `false` at the end of the
+    /// stream.
+    fn skip(&mut self, prefilter: &Prefilter, pos: &mut usize) -> io::Result<bool> {
+        loop {
+            if let Some(i) = prefilter.find(&self.buf, *pos - self.base) {
+                *pos = self.base + i;
+                return Ok(true);
+            }
+            // A literal may begin in the last few bytes
+            let end = self.base + self.buf.len();
+            *pos = (*pos).max(end.saturating_sub(prefilter.width() - 1));
+            if !self.refill(*pos)? {
+                return Ok(false);
+            }
+        }
+    }
+
+    /// The leftmost-longest match starting at or after `at`.
+    fn find_next(&mut self) -> io::Result<Option<ReaderMatch>> {
+        while self.at > self.base + self.buf.len() {
+            let end = self.base + self.buf.len();
+            if !self.refill(end)? {
+                return Ok(None);
+            }
+        }
+        let found = match self.regex.dfas() {
+            Some((forward, reverse)) => self.find_with_dfas(forward, reverse)?,
+            None => self.find_with_threads(self.at)?,
+        };
+        let Some((start, end)) = found else {
+            return Ok(None);
+        };
+        let bytes = self
+            .bytes
+            .then(|| self.buf[start - self.base..end - self.base].to_vec());
+        self.at = if start == end { end + 1 } else { end };
+        Ok(Some(ReaderMatch { start, end, bytes }))
+    }
+
+    /// Runs `forward` from `at` to where the match ends, keeping the bytes
+    /// it reads, and `reverse` back over them. Leaves the search to threads
+    /// once `capacity` bytes are kept.
+    fn find_with_dfas(
+        &mut self,
+        forward: &DFA,
+        reverse: &DFA,
+    ) -> io::Result<Option<(usize, usize)>> {
+        let mut from = self.at;
+        if let Some(prefilter) = self.regex.prefilter() {
+            if !self.skip(prefilter, &mut from)? {
+                return Ok(None);
+            }
+        }
+        let mut pos = from;
+        let mut state = forward.start();
+        let mut end = forward.accepting.contains(state).then_some(pos);
+        while forward.kind(state) != StateKind::Dead {
+            if pos == self.base + self.buf.len() {
+                if pos - from >= self.capacity {
+                    return self.find_with_threads(from);
+                }
+                if !self.refill(from)? {
+                    break;
+                }
+            }
+            let class = forward.classes.get(self.buf[pos - self.base]);
+            state = forward.next(state, class);
+            pos += 1;
+            if forward.accepting.contains(state) {
+                end = Some(pos);
+            }
+        }
+        let Some(end) = end else {
+            return Ok(None);
+        };
+        let len = reverse
+            .longest_match_rev(&self.buf[from - self.base..end - self.base])
+            .expect("a match ends here");
+        Ok(Some((end - len, end)))
+    }
+
+    /// Runs NFA threads from `pos` on, keeping bytes only after a match.
+    fn find_with_threads(&mut self, mut pos: usize) -> io::Result<Option<(usize, usize)>> {
+        let regex = self.regex;
+        let program = regex.program();
+        let mut threads = self.threads.take().unwrap_or_else(|| program.threads());
+        threads.clear();
+        let mut best: Option<(usize, usize)> = None;
+        loop {
+            if best.is_none() {
+                if let Some(prefilter) = regex.prefilter() {
+                    if threads.is_empty() && !self.skip(prefilter, &mut pos)? {
+                        break;
+                    }
+                }
+                program.enter(&mut threads, pos);
+            }
+            if let Some(start) = program.accepted(&threads, false) {
+                // Threads left are leftmost, so any later match is better
+                best = Some((start, pos));
+                threads.retain_until(start);
+            }
+            if threads.is_empty() {
+                break;
+            }
+            if pos == self.base + self.buf.len() {
+                let keep = match best {
+                    _ if self.bytes => threads.earliest().unwrap_or(pos),
+                    Some((_, end)) => end,
+                    None => pos,
+                };
+                if !self.refill(keep)? {
+                    // Branches held to the end of the stream may match too
+                    if let Some(start) = program.accepted(&threads, true) {
+                        best = Some((start, pos));
+                    }
+                    break;
+                }
+            }
+            program.step(&mut threads, self.buf[pos - self.base]);
+            pos += 1;
+        }
+        self.threads = Some(threads);
+        Ok(best)
+    }
+}
+
+impl<R: Read> Iterator for ReaderMatches<'_, R> {
+    type Item = io::Result<ReaderMatch>;
+
+    fn next(&mut self) -> Option<io::Result<ReaderMatch>> {
+        if self.failed {
+            return None;
+        }
+        let result = self.find_next();
+        self.failed = result.is_err();
+        result.transpose()
+    }
+}
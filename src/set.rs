@@ -0,0 +1,152 @@
+use std::sync::Arc;
+
+use crate::{
+    ast::Regex,
+    engine::{
+        compiler,
+        dfa::{Priority, DFA},
+        lazy::{Deferred, Direction},
+        pike::Program,
+        prefilter::Prefilter,
+    },
+    error::{Error, Limits},
+    matcher::{Match, RegexBuilder},
+    overlapping::OverlappingMatches,
+};
+
+/// Many patterns compiled into one DFA, so that a single pass over the
+/// input tells which of them match. Patterns are identified by their
+/// position in the list they were built from.
+#[derive(Debug)]
+pub struct RegexSet {
+    dfa: DFA,
+    /// Skips to where some pattern's prefix literal occurs.
+    prefilter: Option<Prefilter>,
+    /// The leftmost-longest search for where a match of any pattern ends,
+    /// and every pattern reversed for where it starts.
+    forward: Deferred,
+    reverse: Deferred,
+    /// Every pattern in one NFA, for overlapping search and for `find`
+    /// when the DFAs above would be too big.
+    program: Program,
+    len: usize,
+    pub(crate) priority: Priority,
+}
+
+impl RegexSet {
+    /// Builds a set from patterns in the default dialect.
+    pub fn new<I>(patterns: I) -> Result<Self, Error>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        RegexBuilder::new().build_set(patterns)
+    }
+
+    pub fn from_asts(asts: &[Regex], limits: &Limits) -> Result<Self, Error> {
+        // Checks the limits before anything recurses into the ASTs
+        let dfa = compiler::compile_set(asts, limits)?;
+        let patterns: Arc<[Regex]> = Arc::from(asts);
+        Ok(Self {
+            dfa,
+            prefilter: Prefilter::new(&Regex::any_of(asts.iter().cloned())),
+            forward: Deferred::new(patterns.clone(), Direction::Leftmost, limits.max_dfa_states),
+            reverse: Deferred::new(patterns, Direction::Reverse, limits.max_dfa_states),
+            program: Program::new(asts),
+            len: asts.len(),
+            priority: Priority::default(),
+        })
+    }
+
+    /// IDs of the patterns that match the whole of `input`, in increasing
+    /// order.
+    pub fn matches(&self, input: &str) -> Vec<usize> {
+        self.dfa.matching_patterns(input.as_bytes()).to_vec()
+    }
+
+    /// The leftmost match of any pattern in `haystack`. Of the patterns
+    /// matching at that start, the set's `Priority` picks one.
+    pub fn find<'h>(&self, haystack: &'h str) -> Option<Match<'h>> {
+        self.find_at(haystack, 0)
+    }
+
+    /// Successive non-overlapping matches, as for `find`. After an empty
+    /// match the search resumes one byte further on.
+    pub fn find_iter<'s, 'h>(&'s self, haystack: &'h str) -> SetMatches<'s, 'h> {
+        SetMatches {
+            set: self,
+            haystack,
+            at: 0,
+        }
+    }
+
+    /// Every position where a match of some pattern ends, with the
+    /// longest match of each such pattern ending there, in order of end
+    /// and then pattern ID. Matches may overlap. Like the search of a
+    /// single pattern, this runs NFA threads; see `OverlappingMatches`.
+    pub fn find_overlapping_iter<'s, 'h>(
+        &'s self,
+        haystack: &'h str,
+    ) -> OverlappingMatches<'s, 'h> {
+        OverlappingMatches::new(&self.program, haystack)
+    }
+
+    /// The leftmost match starting at or after `at`. Its end, the longest
+    /// of any pattern there, comes from one pass of the forward DFA and
+    /// its start from one of the reverse DFA, or if either would be too
+    /// big, from NFA threads. The set's DFA then picks the pattern from
+    /// the matched bytes alone.
+    fn find_at<'h>(&self, haystack: &'h str, mut at: usize) -> Option<Match<'h>> {
+        let bytes = haystack.as_bytes();
+        if let Some(prefilter) = &self.prefilter {
+            at = prefilter.find(bytes, at)?;
+        }
+        let (start, end) = match (self.forward.dfa(), self.reverse.dfa()) {
+            (Some(forward), Some(reverse)) => {
+                let end = at + forward.longest_match(bytes.get(at..)?)?;
+                let len = reverse
+                    .longest_match_rev(&bytes[at..end])
+                    .expect("a match ends here");
+                (end - len, end)
+            }
+            _ => self.program.find_at(bytes, at)?,
+        };
+        // Every pattern's matches from `start` end by `end`
+        let (pattern, len) = self
+            .dfa
+            .longest_match_with(&bytes[start..end], self.priority)
+            .expect("a pattern matches here");
+        Some(Match {
+            haystack,
+            pattern,
+            start,
+            end: start + len,
+        })
+    }
+
+    /// Number of patterns in the set.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Iterator returned by `RegexSet::find_iter`.
+pub struct SetMatches<'s, 'h> {
+    set: &'s RegexSet,
+    haystack: &'h str,
+    at: usize,
+}
+
+impl<'h> Iterator for SetMatches<'_, 'h> {
+    type Item = Match<'h>;
+
+    fn next(&mut self) -> Option<Match<'h>> {
+        let m = self.set.find_at(self.haystack, self.at)?;
+        self.at = if m.is_empty() { m.end + 1 } else { m.end };
+        Some(m)
+    }
+}
@@ -0,0 +1,91 @@
+use bit_set::BitSet;
+
+use crate::{
+    engine::{dfa::StateKind, lazy::Cache},
+    matcher::Engine,
+};
+
+/// Matches input that arrives in pieces, such as reads from a socket.
+/// Between calls it keeps only where the engine is, so however the input is
+/// split the answer is the same as `AdamRegex::matches` on all of it at
+/// once, and memory does not grow with the input.
+///
+/// For a DFA that is a single state ID. A lazy DFA's state ID is into a
+/// cache of the matcher's own, which holds at most `Limits::max_dfa_states`
+/// states, since the regex's cache may be cleared between calls. The other
+/// engines have no state IDs: bit-parallel keeps a 64-bit set of positions,
+/// and NFA simulation a set of NFA states.
+pub struct Matcher<'r> {
+    engine: &'r Engine,
+    state: State,
+}
+
+enum State {
+    Dfa(usize),
+    Lazy(Box<Cache>, u32),
+    Bits(Option<u64>),
+    Nfa(BitSet),
+}
+
+impl<'r> Matcher<'r> {
+    pub(crate) fn new(engine: &'r Engine) -> Self {
+        let state = match engine {
+            Engine::Dfa(dfa) => State::Dfa(dfa.start()),
+            Engine::BitParallel(_) => State::Bits(None),
+            Engine::Lazy(lazy) => {
+                let mut cache = lazy.cache();
+                let start = lazy.start(&mut cache);
+                State::Lazy(Box::new(cache), start)
+            }
+            Engine::Nfa(nfa) => State::Nfa(nfa.start_set()),
+        };
+        Self { engine, state }
+    }
+
+    /// Reads the next piece of input.
+    pub fn feed(&mut self, chunk: &[u8]) {
+        self.state = match (
+            self.engine,
+            std::mem::replace(&mut self.state, State::Dfa(0)),
+        ) {
+            (Engine::Dfa(dfa), State::Dfa(state)) => State::Dfa(dfa.feed(state, chunk)),
+            (Engine::BitParallel(bits), State::Bits(active)) => {
+                State::Bits(bits.feed(active, chunk))
+            }
+            (Engine::Lazy(lazy), State::Lazy(mut cache, state)) => {
+                let state = lazy.feed(&mut cache, state, chunk);
+                State::Lazy(cache, state)
+            }
+            (Engine::Nfa(nfa), State::Nfa(set)) => State::Nfa(nfa.feed(set, chunk)),
+            _ => unreachable!("the state always belongs to the engine"),
+        };
+    }
+
+    /// Whether the input so far matches.
+    pub fn is_match(&self) -> bool {
+        match (self.engine, &self.state) {
+            (Engine::Dfa(dfa), State::Dfa(state)) => dfa.accepting.contains(*state),
+            (Engine::BitParallel(bits), State::Bits(active)) => bits.is_accepting(*active),
+            (Engine::Lazy(_), State::Lazy(cache, state)) => cache.is_accepting(*state),
+            (Engine::Nfa(nfa), State::Nfa(set)) => nfa.is_accepting(set),
+            _ => unreachable!("the state always belongs to the engine"),
+        }
+    }
+
+    /// Whether no more input can make it match, so the rest of the stream
+    /// need not be read.
+    pub fn is_dead(&self) -> bool {
+        match (self.engine, &self.state) {
+            (Engine::Dfa(dfa), State::Dfa(state)) => dfa.kind(*state) == StateKind::Dead,
+            (_, State::Lazy(cache, state)) => cache.is_dead(*state),
+            (_, State::Bits(active)) => *active == Some(0),
+            (_, State::Nfa(set)) => set.is_empty(),
+            _ => unreachable!("the state always belongs to the engine"),
+        }
+    }
+
+    /// Ends the input and returns whether all of it matched.
+    pub fn finish(self) -> bool {
+        self.is_match()
+    }
+}
@@ -0,0 +1,143 @@
+/// Splits the scalar values in `start..=end` into sequences of byte ranges,
+/// such that a UTF-8 encoded char lies in the range iff its bytes match one
+/// of the returned sequences position by position. Surrogates are skipped.
+pub fn sequences(start: char, end: char) -> Vec<Vec<(u8, u8)>> {
+    let mut out = Vec::new();
+    let mut stack = vec![(start as u32, end as u32)];
+
+    while let Some((s, e)) = stack.pop() {
+        if s > e {
+            continue;
+        }
+
+        // Surrogates have no encoding, so cut them out of the range.
+        if s <= 0xDFFF && e >= 0xD800 {
+            if s < 0xD800 {
+                stack.push((s, 0xD7FF));
+            }
+            if e > 0xDFFF {
+                stack.push((0xE000, e));
+            }
+            continue;
+        }
+
+        // Both ends must encode to the same number of bytes.
+        if let Some(boundary) = [0x7F, 0x7FF, 0xFFFF].into_iter().find(|&b| s <= b && e > b) {
+            stack.push((boundary + 1, e));
+            stack.push((s, boundary));
+            continue;
+        }
+
+        // Each continuation byte must cover its full 0x80..=0xBF span unless
+        // all the bytes before it are fixed.
+        let len = encoded_len(s);
+        let mut split = false;
+        for i in 1..len {
+            let max = (1u32 << (6 * i)) - 1;
+            if s & !max != e & !max {
+                if s & max != 0 {
+                    stack.push(((s | max) + 1, e));
+                    stack.push((s, s | max));
+                    split = true;
+                    break;
+                }
+                if e & max != max {
+                    stack.push((e & !max, e));
+                    stack.push((s, (e & !max) - 1));
+                    split = true;
+                    break;
+                }
+            }
+        }
+        if split {
+            continue;
+        }
+
+        let lo = encode(s);
+        let hi = encode(e);
+        out.push(lo.iter().zip(hi.iter()).map(|(&l, &h)| (l, h)).collect());
+    }
+
+    out
+}
+
+fn encoded_len(cp: u32) -> usize {
+    match cp {
+        0..=0x7F => 1,
+        0x80..=0x7FF => 2,
+        0x800..=0xFFFF => 3,
+        _ => 4,
+    }
+}
+
+fn encode(cp: u32) -> Vec<u8> {
+    let c = char::from_u32(cp).expect("range endpoints are scalar values");
+    let mut buf = [0; 4];
+    c.encode_utf8(&mut buf).as_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod utf8_tests {
+    use super::*;
+
+    fn matches(seqs: &[Vec<(u8, u8)>], c: char) -> bool {
+        let mut buf = [0; 4];
+        let bytes = c.encode_utf8(&mut buf).as_bytes();
+        seqs.iter().any(|seq| {
+            seq.len() == bytes.len()
+                && seq
+                    .iter()
+                    .zip(bytes)
+                    .all(|(&(lo, hi), &b)| lo <= b && b <= hi)
+        })
+    }
+
+    #[test]
+    fn ascii_range_is_single_sequence() {
+        assert_eq!(sequences('a', 'z'), vec![vec![(b'a', b'z')]]);
+    }
+
+    #[test]
+    fn two_byte_range() {
+        assert_eq!(
+            sequences('α', 'ω'),
+            vec![
+                vec![(0xCE, 0xCE), (0xB1, 0xBF)],
+                vec![(0xCF, 0xCF), (0x80, 0x89)]
+            ]
+        );
+    }
+
+    #[test]
+    fn full_range_covers_every_scalar_value() {
+        let seqs = sequences('\0', char::MAX);
+        for c in [
+            '\0',
+            'a',
+            '\u{7F}',
+            '\u{80}',
+            'é',
+            '\u{7FF}',
+            '\u{800}',
+            '€',
+            '\u{D7FF}',
+            '\u{E000}',
+            '\u{FFFF}',
+            '\u{10000}',
+            '😀',
+            char::MAX,
+        ] {
+            assert!(matches(&seqs, c), "{:?} not covered", c);
+        }
+    }
+
+    #[test]
+    fn sequences_are_exact() {
+        let (start, end) = ('\u{3FF}', '\u{1234}');
+        let seqs = sequences(start, end);
+        for cp in 0x300..0x1300 {
+            let c = char::from_u32(cp).unwrap();
+            assert_eq!(matches(&seqs, c), (start..=end).contains(&c), "{:?}", c);
+        }
+    }
+}
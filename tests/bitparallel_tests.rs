@@ -0,0 +1,75 @@
+use adam_regex::ast::Regex;
+use adam_regex::engine::bitparallel::{BitParallel, MAX_POSITIONS};
+use adam_regex::engine::compiler;
+use adam_regex::error::Limits;
+use adam_regex::matcher::AdamRegex;
+use adam_regex::parser::parser::parse;
+
+mod common;
+
+fn bit_parallel(pattern: &str) -> BitParallel {
+    let ast = parse(pattern);
+    compiler::compile_bit_parallel(&ast, &Limits::default())
+        .unwrap()
+        .unwrap()
+}
+
+#[test]
+fn agrees_with_dfa() {
+    let inputs = common::inputs();
+    for pattern in common::PATTERNS {
+        let dfa = AdamRegex::builder()
+            .limits(Limits::unlimited())
+            .build(pattern)
+            .unwrap();
+        let bits = bit_parallel(pattern);
+        let eager = compiler::compile(&parse(pattern), true);
+        for input in &inputs {
+            assert_eq!(
+                bits.matches(input),
+                dfa.matches(input),
+                "{} on {:?}",
+                pattern,
+                input
+            );
+            assert_eq!(
+                bits.shortest_match(input.as_bytes()),
+                eager.shortest_match(input.as_bytes()),
+                "{} on {:?}",
+                pattern,
+                input
+            );
+        }
+    }
+}
+
+#[test]
+fn positions_span_several_chunks() {
+    // 60 positions, so the follow sets cross every table chunk
+    let pattern = "(ab)*".to_string() + &"c".repeat(58);
+    let bits = bit_parallel(&pattern);
+    assert!(bits.matches(&"c".repeat(58)));
+    assert!(bits.matches(&("abab".to_string() + &"c".repeat(58))));
+    assert!(!bits.matches(&("aba".to_string() + &"c".repeat(58))));
+    assert!(!bits.matches(&"c".repeat(57)));
+}
+
+#[test]
+fn large_patterns_are_not_bit_parallel() {
+    let ast = Regex::literal(&"a".repeat(MAX_POSITIONS + 1));
+    let bits = compiler::compile_bit_parallel(&ast, &Limits::default()).unwrap();
+    assert!(bits.is_none());
+}
+
+#[test]
+fn chosen_when_the_dfa_is_large() {
+    // needs 2^21 DFA states, far over the default limit
+    let ab = Regex::literal("a").or("b");
+    let ast = ab.clone().star().then("a").then(ab.repeat(20..=20));
+    let re = AdamRegex::builder().build_ast(&ast).unwrap();
+
+    let hit = "b".repeat(100) + "a" + &"b".repeat(20);
+    let miss = "b".repeat(100) + "a" + &"b".repeat(21);
+    assert!(re.matches(&hit));
+    assert!(!re.matches(&miss));
+}
@@ -0,0 +1,143 @@
+use adam_regex::ast::Regex::{self, *};
+use adam_regex::builder::class;
+use adam_regex::matcher::AdamRegex;
+
+fn b(r: Regex) -> Box<Regex> {
+    Box::new(r)
+}
+
+#[test]
+fn literal_is_left_nested_concat() {
+    assert_eq!(
+        Regex::literal("abc"),
+        Concat(b(Concat(b(Byte(b'a')), b(Byte(b'b')))), b(Byte(b'c')))
+    );
+    assert_eq!(Regex::literal(""), Empty);
+}
+
+#[test]
+fn literal_does_not_interpret_syntax() {
+    let re = AdamRegex::from_ast(&Regex::literal("a.b*"));
+    assert!(re.matches("a.b*"));
+    assert!(!re.matches("axb"));
+    assert!(!re.matches("a.bbb"));
+}
+
+#[test]
+fn literal_lowers_utf8() {
+    assert_eq!(Regex::literal("é"), Concat(b(Byte(0xC3)), b(Byte(0xA9))));
+
+    let re = AdamRegex::from_ast(&Regex::literal("naïve λ"));
+    assert!(re.matches("naïve λ"));
+    assert!(!re.matches("naive λ"));
+}
+
+#[test]
+fn any_of_keywords() {
+    let re = AdamRegex::from_ast(&Regex::any_of(["GET", "POST", "PUT"]));
+    assert!(re.matches("GET"));
+    assert!(re.matches("POST"));
+    assert!(re.matches("PUT"));
+    assert!(!re.matches("PATCH"));
+    assert!(!re.matches(""));
+}
+
+#[test]
+fn any_of_empty_matches_nothing() {
+    let re = AdamRegex::from_ast(&Regex::any_of(Vec::<Regex>::new()));
+    assert!(!re.matches(""));
+    assert!(!re.matches("a"));
+}
+
+#[test]
+fn then_and_or_chain() {
+    let ast = Regex::literal("ab")
+        .or("cd")
+        .then(Regex::literal("!").optional());
+    let re = AdamRegex::from_ast(&ast);
+    assert!(re.matches("ab"));
+    assert!(re.matches("cd!"));
+    assert!(!re.matches("abcd"));
+    assert!(!re.matches("!"));
+}
+
+#[test]
+fn repeat_bounded() {
+    let re = AdamRegex::from_ast(&Regex::literal("ab").repeat(2..=3));
+    assert!(!re.matches("ab"));
+    assert!(re.matches("abab"));
+    assert!(re.matches("ababab"));
+    assert!(!re.matches("abababab"));
+}
+
+#[test]
+fn repeat_exact_and_zero() {
+    let re = AdamRegex::from_ast(&Regex::literal("x").repeat(3..=3));
+    assert!(re.matches("xxx"));
+    assert!(!re.matches("xx"));
+    assert!(!re.matches("xxxx"));
+
+    assert_eq!(Regex::literal("x").repeat(0..=0), Empty);
+}
+
+#[test]
+fn repeat_unbounded() {
+    let re = AdamRegex::from_ast(&Regex::literal("a").repeat(2..));
+    assert!(!re.matches("a"));
+    assert!(re.matches("aa"));
+    assert!(re.matches("aaaaaa"));
+
+    assert_eq!(Byte(b'a').repeat(..), Star(b(Byte(b'a'))));
+}
+
+#[test]
+#[should_panic(expected = "exceeds maximum")]
+fn repeat_inverted_range_panics() {
+    let (min, max) = (3, 2);
+    let _ = Byte(b'a').repeat(min..=max);
+}
+
+#[test]
+fn ascii_class() {
+    assert_eq!(class('a'..='z'), Class(vec![(b'a', b'z')]));
+
+    let re = AdamRegex::from_ast(&class('a'..='z').plus());
+    assert!(re.matches("hello"));
+    assert!(!re.matches("Hello"));
+    assert!(!re.matches(""));
+}
+
+#[test]
+fn unicode_class() {
+    let re = AdamRegex::from_ast(&class('α'..='ω'));
+    assert!(re.matches("α"));
+    assert!(re.matches("λ"));
+    assert!(re.matches("ω"));
+    assert!(!re.matches("Ω"));
+    assert!(!re.matches("a"));
+    assert!(!re.matches("\u{3B1}\u{3B1}"));
+}
+
+#[test]
+fn class_of_merges_ranges() {
+    assert_eq!(
+        Regex::class_of(['a'..='f', 'd'..='k', '0'..='9']),
+        Class(vec![(b'0', b'9'), (b'a', b'k')])
+    );
+}
+
+#[test]
+fn any_char_matches_one_scalar_value() {
+    let re = AdamRegex::from_ast(&Regex::any_char());
+    assert!(re.matches("a"));
+    assert!(re.matches("é"));
+    assert!(re.matches("😀"));
+    assert!(!re.matches("ab"));
+    assert!(!re.matches(""));
+}
+
+#[test]
+fn built_patterns_display() {
+    assert_eq!(class('a'..='z').plus().to_string(), "[a-z]+");
+    assert_eq!(Regex::literal("ab").or("c").to_string(), "ab|c");
+}
@@ -0,0 +1,36 @@
+// Each test binary uses only some of what is here
+#![allow(dead_code)]
+
+/// Patterns that every engine should agree on, over `inputs`.
+pub const PATTERNS: [&str; 11] = [
+    "(a|b)*abb",
+    "a+b?a*",
+    "(ab|ba)*",
+    "((a*)*b)*",
+    "a.b",
+    "b*",
+    "(a?b?)*c",
+    "(a|b)*a(a|b)(a|b)(a|b)",
+    "(a|c)+b?",
+    "a?",
+    "c+",
+];
+
+/// Every string over `a`, `b` and `c` of at most six chars.
+pub fn inputs() -> Vec<String> {
+    strings(&['a', 'b', 'c'], 6)
+}
+
+/// Every string over `alphabet` of at most `max_len` chars, shortest first.
+pub fn strings(alphabet: &[char], max_len: u32) -> Vec<String> {
+    let mut strings = vec![String::new()];
+    for len in 1..=max_len {
+        for n in 0..alphabet.len().pow(len) {
+            let s: String = (0..len)
+                .map(|i| alphabet[n / alphabet.len().pow(i) % alphabet.len()])
+                .collect();
+            strings.push(s);
+        }
+    }
+    strings
+}
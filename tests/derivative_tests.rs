@@ -0,0 +1,88 @@
+use adam_regex::ast::Regex;
+use adam_regex::engine::{compiler, derivative};
+
+mod common;
+
+/// Every AST of depth at most two over `a`, `b`, `[a-b]` and the empty
+/// string.
+fn small_asts() -> Vec<Regex> {
+    let leaves = vec![
+        Regex::literal("a"),
+        Regex::literal("b"),
+        Regex::byte_class([(b'a', b'b')]),
+        Regex::Empty,
+    ];
+
+    let mut asts = leaves.clone();
+    for x in &leaves {
+        asts.push(x.clone().star());
+        asts.push(x.clone().plus());
+        asts.push(x.clone().optional());
+        for y in &leaves {
+            asts.push(x.clone().then(y.clone()));
+            asts.push(x.clone().or(y.clone()));
+        }
+    }
+
+    let level1 = asts.clone();
+    for x in &level1 {
+        asts.push(x.clone().star());
+        for y in &level1 {
+            asts.push(x.clone().then(y.clone()));
+            asts.push(x.clone().or(y.clone()));
+        }
+    }
+    asts
+}
+
+/// Both pipelines must agree on every input and minimize to the same
+/// number of states.
+fn check(ast: &Regex) {
+    let expected = compiler::compile(ast, true);
+    let actual = derivative::compile(ast).minimize();
+
+    assert_eq!(actual.state_count(), expected.state_count(), "{}", ast);
+    for input in common::inputs() {
+        assert_eq!(
+            actual.matches(&input),
+            expected.matches(&input),
+            "{} on {:?}",
+            ast,
+            input
+        );
+    }
+}
+
+#[test]
+fn agrees_with_subset_construction() {
+    for ast in small_asts() {
+        check(&ast);
+    }
+}
+
+#[test]
+fn agrees_on_larger_patterns() {
+    let ab = Regex::literal("a").or("b");
+    let cases = [
+        ab.clone().star().then("a").then(ab.clone().repeat(4..=4)),
+        Regex::any_char().star().then("abc").then(Regex::Dot.star()),
+        Regex::literal("ab")
+            .repeat(2..5)
+            .then(Regex::literal("c").optional()),
+        Regex::class_of(['a'..='c', 'é'..='ü']).plus(),
+        Regex::never().or("a").star(),
+    ];
+    for ast in &cases {
+        check(ast);
+    }
+}
+
+#[test]
+fn similar_derivatives_are_merged() {
+    // every derivative of these is a* again, so only the dead state and
+    // one live state are built, before any minimization
+    let a = || Regex::literal("a");
+    for ast in [a().star().star(), a().or(a()).star()] {
+        assert_eq!(derivative::compile(&ast).state_count(), 2, "{}", ast);
+    }
+}
@@ -0,0 +1,126 @@
+use adam_regex::error::Error;
+use adam_regex::matcher::AdamRegex;
+use adam_regex::parser::Dialect;
+
+fn ere(pattern: &str) -> AdamRegex {
+    try_ere(pattern).unwrap()
+}
+
+fn try_ere(pattern: &str) -> Result<AdamRegex, Error> {
+    AdamRegex::builder().dialect(Dialect::Ere).build(pattern)
+}
+
+#[test]
+fn punctuation_is_literal() {
+    let re = ere("user-name_1@host:8080/path");
+    assert!(re.matches("user-name_1@host:8080/path"));
+}
+
+#[test]
+fn intervals() {
+    let re = ere("[0-9]{1,3}(\\.[0-9]{1,3}){3}");
+    assert!(re.matches("192.168.0.1"));
+    assert!(re.matches("1.2.3.4"));
+    assert!(!re.matches("1.2.3"));
+    assert!(!re.matches("1234.1.1.1"));
+
+    let re = ere("ab{2,}");
+    assert!(!re.matches("ab"));
+    assert!(re.matches("abb"));
+    assert!(re.matches("abbbbb"));
+
+    let re = ere("x{0}y");
+    assert!(re.matches("y"));
+    assert!(!re.matches("xy"));
+}
+
+#[test]
+fn bracket_expressions() {
+    let re = ere("[[:alpha:]_][[:alnum:]_]*");
+    assert!(re.matches("_foo42"));
+    assert!(!re.matches("42foo"));
+
+    let re = ere("[^]]+");
+    assert!(re.matches("abc"));
+    assert!(!re.matches("a]c"));
+
+    let re = ere("[^a-z]");
+    assert!(re.matches("é"));
+    assert!(re.matches("A"));
+    assert!(!re.matches("q"));
+
+    let re = ere("[αβ]");
+    assert!(re.matches("β"));
+    assert!(!re.matches("γ"));
+}
+
+#[test]
+fn dot_matches_a_character() {
+    let re = ere("a.b");
+    assert!(re.matches("aéb"));
+    assert!(re.matches("a€b"));
+    assert!(!re.matches("aéeb"));
+    // the same as a negated bracket
+    let bracket = ere("a[^x]b");
+    for input in ["aéb", "a😀b", "ab", "a\nb", "aéeb"] {
+        assert_eq!(re.matches(input), bracket.matches(input), "{:?}", input);
+    }
+    let m = re.find("xxa😀bxx").unwrap();
+    assert_eq!(m.range(), 2..8);
+}
+
+#[test]
+fn escaped_specials() {
+    let re = ere("\\(a\\)\\*\\{\\}\\[\\]\\^\\$\\\\");
+    assert!(re.matches("(a)*{}[]^$\\"));
+}
+
+#[test]
+fn anchors_at_the_ends() {
+    let re = ere("^(GET|POST) /[^ ]*$");
+    assert!(re.matches("GET /index.html"));
+    assert!(!re.matches("PUT /index.html"));
+
+    let re = ere("^abc$|^x");
+    assert!(re.matches("abc"));
+    assert!(re.matches("x"));
+
+    let re = ere("^$");
+    assert!(re.matches(""));
+    assert!(!re.matches("a"));
+}
+
+#[test]
+fn interior_anchors_are_rejected() {
+    assert!(matches!(try_ere("a^b"), Err(Error::Syntax(_))));
+    assert!(matches!(try_ere("a$b"), Err(Error::Syntax(_))));
+    assert!(matches!(try_ere("(^a)"), Err(Error::Syntax(_))));
+}
+
+#[test]
+fn perl_escapes_are_rejected() {
+    assert!(matches!(try_ere("\\d+"), Err(Error::Syntax(_))));
+    assert!(matches!(try_ere("\\bword\\b"), Err(Error::Syntax(_))));
+}
+
+#[test]
+fn leftmost_longest_alternation() {
+    // A backtracking engine would commit to "a" and fail on the rest.
+    let re = ere("(a|ab)(c|bcd)");
+    assert!(re.matches("abcd"));
+    assert!(re.matches("ac"));
+}
+
+#[test]
+fn default_dialect_is_unchanged() {
+    assert!(matches!(AdamRegex::from_str("[a]"), Err(Error::Syntax(_))));
+    assert!(AdamRegex::from_str("a{2}").is_err());
+}
+
+#[test]
+fn interval_expansion_respects_node_limit() {
+    assert!(matches!(
+        try_ere("((a{255}){255}){255}"),
+        Err(Error::TooManyAstNodes { .. })
+    ));
+}
@@ -0,0 +1,143 @@
+use adam_regex::glob::{self, GlobOptions};
+use adam_regex::matcher::AdamRegex;
+
+fn glob(pattern: &str) -> AdamRegex {
+    AdamRegex::from_ast(&glob::parse(pattern).unwrap())
+}
+
+fn glob_with(pattern: &str, options: GlobOptions) -> AdamRegex {
+    AdamRegex::from_ast(&glob::parse_with(pattern, &options).unwrap())
+}
+
+#[test]
+fn star_stays_within_component() {
+    let re = glob("src/*.rs");
+    assert!(re.matches("src/lib.rs"));
+    assert!(re.matches("src/.rs"));
+    assert!(!re.matches("src/engine/dfa.rs"));
+    assert!(!re.matches("src/lib.rsx"));
+}
+
+#[test]
+fn question_matches_one_char() {
+    let re = glob("file?.txt");
+    assert!(re.matches("file1.txt"));
+    assert!(re.matches("fileé.txt"));
+    assert!(!re.matches("file.txt"));
+    assert!(!re.matches("file12.txt"));
+    assert!(!re.matches("file/.txt"));
+}
+
+#[test]
+fn double_star_crosses_directories() {
+    let re = glob("src/**/*.rs");
+    assert!(re.matches("src/lib.rs"));
+    assert!(re.matches("src/engine/dfa.rs"));
+    assert!(re.matches("src/a/b/c/d.rs"));
+    assert!(!re.matches("tests/lib.rs"));
+
+    let re = glob("**");
+    assert!(re.matches(""));
+    assert!(re.matches("a/b/c"));
+
+    let re = glob("target/**");
+    assert!(re.matches("target/debug/build"));
+    assert!(!re.matches("targets/x"));
+}
+
+#[test]
+fn double_star_inside_component_is_single_star() {
+    let re = glob("a**b");
+    assert!(re.matches("ab"));
+    assert!(re.matches("axxb"));
+    assert!(!re.matches("a/b"));
+}
+
+#[test]
+fn character_classes() {
+    let re = glob("[a-c]x[!0-9]");
+    assert!(re.matches("axy"));
+    assert!(re.matches("cxZ"));
+    assert!(!re.matches("dxy"));
+    assert!(!re.matches("ax5"));
+    assert!(!re.matches("ax/"));
+
+    let re = glob("[]-]");
+    assert!(re.matches("]"));
+    assert!(re.matches("-"));
+    assert!(!re.matches("a"));
+}
+
+#[test]
+fn brace_alternation() {
+    let re = glob("*.{rs,toml}");
+    assert!(re.matches("lib.rs"));
+    assert!(re.matches("Cargo.toml"));
+    assert!(!re.matches("Cargo.lock"));
+
+    let re = glob("{src/**/,}*.{c,h{,pp}}");
+    assert!(re.matches("main.c"));
+    assert!(re.matches("src/x/y.hpp"));
+    assert!(re.matches("src/y.h"));
+    assert!(!re.matches("src/y.cpp"));
+}
+
+#[test]
+fn escapes_are_literal() {
+    let re = glob("\\*\\?");
+    assert!(re.matches("*?"));
+    assert!(!re.matches("ab"));
+}
+
+#[test]
+fn case_insensitive() {
+    let options = GlobOptions {
+        case_insensitive: true,
+        ..Default::default()
+    };
+    let re = glob_with("*.JPG", options.clone());
+    assert!(re.matches("photo.jpg"));
+    assert!(re.matches("photo.JpG"));
+    assert!(!re.matches("photo.png"));
+
+    let re = glob_with("[a-c]", options.clone());
+    assert!(re.matches("B"));
+    assert!(!re.matches("D"));
+
+    let re = glob_with("straße", options);
+    assert!(re.matches("STRAßE"));
+}
+
+#[test]
+fn literal_leading_dot() {
+    let options = GlobOptions {
+        literal_leading_dot: true,
+        ..Default::default()
+    };
+    let re = glob_with("*", options.clone());
+    assert!(re.matches("file"));
+    assert!(re.matches("file.txt"));
+    assert!(!re.matches(".hidden"));
+
+    let re = glob_with("src/?[a-z]*", options.clone());
+    assert!(!re.matches("src/.git"));
+    assert!(re.matches("src/ag"));
+
+    let re = glob_with("**/*.rs", options.clone());
+    assert!(re.matches("a/b/c.rs"));
+    assert!(!re.matches("a/.cache/c.rs"));
+
+    let re = glob_with(".*", options);
+    assert!(re.matches(".hidden"));
+
+    let re = glob("*");
+    assert!(re.matches(".hidden"));
+}
+
+#[test]
+fn malformed_globs_are_errors() {
+    assert!(glob::parse("[abc").is_err());
+    assert!(glob::parse("{a,b").is_err());
+    assert!(glob::parse("abc\\").is_err());
+    assert!(glob::parse("[z-a]").is_err());
+}
@@ -0,0 +1,54 @@
+use adam_regex::engine::compiler::{self, Construction};
+use adam_regex::error::Limits;
+use adam_regex::matcher::AdamRegex;
+use adam_regex::parser::parser::parse;
+
+mod common;
+
+#[test]
+fn agrees_with_thompson() {
+    let inputs = common::inputs();
+    for pattern in common::PATTERNS {
+        let thompson = AdamRegex::from_str(pattern).unwrap();
+        let glushkov = AdamRegex::builder()
+            .construction(Construction::Glushkov)
+            .build(pattern)
+            .unwrap();
+        for input in &inputs {
+            assert_eq!(
+                glushkov.matches(input),
+                thompson.matches(input),
+                "{} on {:?}",
+                pattern,
+                input
+            );
+        }
+    }
+}
+
+#[test]
+fn minimizes_to_the_same_dfa_size() {
+    let limits = Limits::default();
+    for pattern in common::PATTERNS {
+        let ast = parse(pattern);
+        let thompson = compiler::compile_using(&ast, Construction::Thompson, true, &limits);
+        let glushkov = compiler::compile_using(&ast, Construction::Glushkov, true, &limits);
+        assert_eq!(
+            glushkov.unwrap().state_count(),
+            thompson.unwrap().state_count(),
+            "{}",
+            pattern
+        );
+    }
+}
+
+#[test]
+fn works_with_the_lazy_dfa() {
+    let re = AdamRegex::builder()
+        .construction(Construction::Glushkov)
+        .lazy(true)
+        .build("(a|b)*a(a|b)(a|b)")
+        .unwrap();
+    assert!(re.matches("bbabb"));
+    assert!(!re.matches("bbbab"));
+}
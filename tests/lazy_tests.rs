@@ -0,0 +1,106 @@
+use adam_regex::ast::Regex;
+use adam_regex::engine::compiler::{self, Construction};
+use adam_regex::error::{Error, Limits};
+use adam_regex::matcher::AdamRegex;
+use adam_regex::parser::parser::parse;
+
+mod common;
+
+fn lazy(pattern: &str) -> AdamRegex {
+    AdamRegex::builder().lazy(true).build(pattern).unwrap()
+}
+
+fn lazy_with_capacity(ast: &Regex, max_dfa_states: usize) -> AdamRegex {
+    AdamRegex::builder()
+        .lazy(true)
+        .limits(Limits {
+            max_dfa_states,
+            ..Limits::default()
+        })
+        .build_ast(ast)
+        .unwrap()
+}
+
+/// `(a|b)*a(a|b){n}`: the n+1'th byte from the end is an `a`. Its DFA
+/// needs 2^(n+1) states.
+fn nth_from_end(n: usize) -> Regex {
+    let ab = Regex::literal("a").or("b");
+    ab.clone().star().then("a").then(ab.repeat(n..=n))
+}
+
+#[test]
+fn agrees_with_eager_dfa() {
+    for pattern in common::PATTERNS {
+        let eager = AdamRegex::from_str(pattern).unwrap();
+        let lazy = lazy(pattern);
+        let ast = parse(pattern);
+        let dfa = compiler::compile(&ast, true);
+        let lazy_dfa =
+            compiler::compile_lazy(&ast, Construction::Thompson, &Limits::default()).unwrap();
+        for input in common::inputs() {
+            assert_eq!(
+                eager.matches(&input),
+                lazy.matches(&input),
+                "pattern {} on {:?}",
+                pattern,
+                input
+            );
+            assert_eq!(
+                lazy_dfa.shortest_match(input.as_bytes()),
+                dfa.shortest_match(input.as_bytes()),
+                "pattern {} on {:?}",
+                pattern,
+                input
+            );
+        }
+    }
+}
+
+#[test]
+fn repeated_matches_reuse_the_cache() {
+    let re = lazy("(a|b)*c");
+    for _ in 0..3 {
+        assert!(re.matches("ababc"));
+        assert!(!re.matches("ababa"));
+    }
+}
+
+#[test]
+fn exponential_pattern_compiles_lazily() {
+    let ast = nth_from_end(20);
+    assert!(matches!(
+        compiler::compile_with_limits(&ast, true, &Limits::default()),
+        Err(Error::TooManyDfaStates { .. })
+    ));
+
+    let re = lazy_with_capacity(&ast, 10_000);
+    let hit = "b".repeat(100) + "a" + &"b".repeat(20);
+    let miss = "b".repeat(100) + "a" + &"b".repeat(21);
+    assert!(re.matches(&hit));
+    assert!(!re.matches(&miss));
+}
+
+#[test]
+fn small_cache_is_cleared_and_rebuilt() {
+    let ast = nth_from_end(6);
+    let eager = AdamRegex::from_ast(&ast);
+    let re = lazy_with_capacity(&ast, 4);
+    // long enough to reach the byte that decides
+    for input in common::strings(&['a', 'b'], 8) {
+        assert_eq!(eager.matches(&input), re.matches(&input), "{:?}", input);
+    }
+}
+
+#[test]
+fn thrashing_falls_back_to_nfa_simulation() {
+    let ast = nth_from_end(12);
+    let re = lazy_with_capacity(&ast, 2);
+    let mut input: String = (0..2000)
+        .map(|i| if i * 7 % 5 < 2 { 'a' } else { 'b' })
+        .collect();
+    input.push('a');
+    input.push_str(&"b".repeat(12));
+    assert!(re.matches(&input));
+    input.push('b');
+    assert!(!re.matches(&input));
+}
@@ -0,0 +1,120 @@
+use adam_regex::error::{Error, Limits};
+use adam_regex::like::{self, LikeOptions};
+use adam_regex::matcher::AdamRegex;
+
+fn like(pattern: &str) -> AdamRegex {
+    like::compile(pattern, &LikeOptions::default(), &Limits::default()).unwrap()
+}
+
+#[test]
+fn percent_matches_any_sequence() {
+    let re = like("abc%");
+    assert!(re.matches("abc"));
+    assert!(re.matches("abcdef"));
+    assert!(!re.matches("xabc"));
+
+    let re = like("%needle%");
+    assert!(re.matches("needle"));
+    assert!(re.matches("haystack with a needle in it"));
+    assert!(!re.matches("needl"));
+}
+
+#[test]
+fn underscore_matches_one_character() {
+    let re = like("a_c");
+    assert!(re.matches("abc"));
+    assert!(re.matches("aλc"));
+    assert!(!re.matches("ac"));
+    assert!(!re.matches("abbc"));
+}
+
+#[test]
+fn combined_wildcards() {
+    let re = like("abc%_x");
+    assert!(re.matches("abcZx"));
+    assert!(re.matches("abc123x"));
+    assert!(!re.matches("abcx"));
+}
+
+#[test]
+fn regex_syntax_is_literal() {
+    let re = like("a.b*(c|d)");
+    assert!(re.matches("a.b*(c|d)"));
+    assert!(!re.matches("axbc"));
+}
+
+#[test]
+fn default_escape_is_backslash() {
+    let re = like("100\\%");
+    assert!(re.matches("100%"));
+    assert!(!re.matches("1000"));
+
+    let re = like("a\\_b\\\\");
+    assert!(re.matches("a_b\\"));
+    assert!(!re.matches("axb\\"));
+}
+
+#[test]
+fn custom_escape_character() {
+    let options = LikeOptions {
+        escape: Some('!'),
+        ..Default::default()
+    };
+    let re = like::compile("!%%!_!!\\", &options, &Limits::default()).unwrap();
+    assert!(re.matches("%anything_!\\"));
+    assert!(!re.matches("anything_!\\"));
+}
+
+#[test]
+fn no_escape_character() {
+    let options = LikeOptions {
+        escape: None,
+        ..Default::default()
+    };
+    let re = like::compile("a\\%", &options, &Limits::default()).unwrap();
+    assert!(re.matches("a\\"));
+    assert!(re.matches("a\\bc"));
+}
+
+#[test]
+fn ilike_ignores_case() {
+    let options = LikeOptions {
+        case_insensitive: true,
+        ..Default::default()
+    };
+    let re = like::compile("Hello%WORLD", &options, &Limits::default()).unwrap();
+    assert!(re.matches("hello, world"));
+    assert!(re.matches("HELLO world"));
+    assert!(!re.matches("help world"));
+
+    let re = like::compile("ΣΑ_", &options, &Limits::default()).unwrap();
+    assert!(re.matches("σαx"));
+}
+
+#[test]
+fn invalid_patterns_are_errors() {
+    assert!(like::parse("abc\\").is_err());
+    assert!(like::parse("a\\bc").is_err());
+
+    let options = LikeOptions {
+        escape: Some('%'),
+        ..Default::default()
+    };
+    assert!(like::parse_with("a", &options).is_err());
+}
+
+#[test]
+fn limits_bound_compilation() {
+    // `_` is any char, so the DFA must remember the last 18 of them
+    let pattern = "%a".to_string() + &"_".repeat(18);
+    let re = like(&pattern);
+    assert!(re.matches(&("xa".to_string() + &"b".repeat(18))));
+    assert!(!re.matches(&"b".repeat(19)));
+
+    let limits = Limits {
+        max_nfa_states: 10,
+        ..Limits::default()
+    };
+    let err = like::compile(&pattern, &LikeOptions::default(), &limits).err();
+    assert_eq!(err, Some(Error::TooManyNfaStates { limit: 10 }));
+}
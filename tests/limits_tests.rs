@@ -0,0 +1,143 @@
+use adam_regex::ast::Regex;
+use adam_regex::engine::compiler;
+use adam_regex::error::{Error, Limits};
+use adam_regex::matcher::AdamRegex;
+use adam_regex::set::RegexSet;
+
+fn limits() -> Limits {
+    Limits::default()
+}
+
+#[test]
+fn deeply_nested_groups_are_rejected() {
+    let pattern = "(".repeat(100_000) + "a" + &")".repeat(100_000);
+    let err = AdamRegex::from_str(&pattern).err().unwrap();
+    assert_eq!(
+        err,
+        Error::NestingTooDeep {
+            limit: limits().max_depth
+        }
+    );
+}
+
+#[test]
+fn nested_groups_within_limit_compile() {
+    let pattern = "(".repeat(200) + "a" + &")".repeat(200);
+    let re = AdamRegex::from_str(&pattern).unwrap();
+    assert!(re.matches("a"));
+}
+
+#[test]
+fn stacked_operators_are_rejected() {
+    let pattern = "a".to_string() + &"*".repeat(100_000);
+    let err = AdamRegex::from_str(&pattern).err().unwrap();
+    assert!(matches!(err, Error::NestingTooDeep { .. }));
+}
+
+#[test]
+fn custom_depth_limit() {
+    let builder = AdamRegex::builder().limits(Limits {
+        max_depth: 3,
+        ..limits()
+    });
+    assert!(builder.build("a*").is_ok());
+    assert_eq!(
+        builder.build("((a*)*)*").err(),
+        Some(Error::NestingTooDeep { limit: 3 })
+    );
+    assert_eq!(
+        builder.build("((((a))))").err(),
+        Some(Error::NestingTooDeep { limit: 3 })
+    );
+}
+
+#[test]
+fn ast_node_limit() {
+    let builder = AdamRegex::builder().limits(Limits {
+        max_ast_nodes: 10,
+        ..limits()
+    });
+    assert!(builder.build("a|b|c").is_ok());
+    assert_eq!(
+        builder.build("abcdefghij").err(),
+        Some(Error::TooManyAstNodes { limit: 10 })
+    );
+}
+
+#[test]
+fn ast_limits_apply_to_built_asts() {
+    let shallow = Limits {
+        max_depth: 10,
+        ..limits()
+    };
+    let builder = AdamRegex::builder().limits(shallow.clone());
+    let deep = (0..20).fold(Regex::literal("a"), |acc, _| acc.optional());
+    assert_eq!(
+        builder.build_ast(&deep).err(),
+        Some(Error::NestingTooDeep { limit: 10 })
+    );
+    let asts = [Regex::literal("a"), deep];
+    assert_eq!(
+        RegexSet::from_asts(&asts, &shallow).err(),
+        Some(Error::NestingTooDeep { limit: 10 })
+    );
+}
+
+#[test]
+fn nfa_state_limit() {
+    let builder = AdamRegex::builder().limits(Limits {
+        max_nfa_states: 8,
+        ..limits()
+    });
+    assert!(builder.build("ab").is_ok());
+    assert_eq!(
+        builder.build("abcdefghij").err(),
+        Some(Error::TooManyNfaStates { limit: 8 })
+    );
+}
+
+#[test]
+fn exponential_dfa_falls_back_to_nfa() {
+    let ab = Regex::literal("a").or("b");
+    // too many positions to run bit-parallel instead
+    let ast = ab.clone().star().then("a").then(ab.repeat(40..=40));
+    let err = compiler::compile_with_limits(&ast, true, &limits()).err();
+    assert_eq!(
+        err,
+        Some(Error::TooManyDfaStates {
+            limit: limits().max_dfa_states
+        })
+    );
+
+    let re = AdamRegex::builder().build_ast(&ast).unwrap();
+    let hit = "b".repeat(100) + "a" + &"b".repeat(40);
+    let miss = "b".repeat(100) + "a" + &"b".repeat(41);
+    assert!(re.matches(&hit));
+    assert!(!re.matches(&miss));
+}
+
+#[test]
+fn syntax_errors_are_typed() {
+    assert!(matches!(
+        AdamRegex::from_str("(ab"),
+        Err(Error::Syntax(msg)) if msg == "Expected ')'"
+    ));
+    assert!(matches!(AdamRegex::from_str("a&b"), Err(Error::Syntax(_))));
+    assert!(matches!(AdamRegex::from_str("a)"), Err(Error::Syntax(_))));
+}
+
+#[test]
+fn unlimited_builder() {
+    let re = AdamRegex::builder()
+        .limits(Limits::unlimited())
+        .build("(a|b)*c")
+        .unwrap();
+    assert!(re.matches("ababc"));
+}
+
+#[test]
+fn long_literals_are_not_nested() {
+    let pattern = "a".repeat(300);
+    let re = AdamRegex::from_str(&pattern).unwrap();
+    assert!(re.matches(&pattern));
+}
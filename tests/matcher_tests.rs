@@ -1,6 +1,9 @@
 use adam_regex::ast::Regex::{self, *};
 use adam_regex::engine::compiler;
+use adam_regex::engine::dfa::{StateKind, DEAD};
+use adam_regex::error::Limits;
 use adam_regex::matcher::AdamRegex;
+use adam_regex::parser::parser::parse;
 
 fn b(r: Regex) -> Box<Regex> {
     Box::new(r)
@@ -112,15 +115,15 @@ fn optional_matches_zero_or_one() {
 
 #[test]
 fn test_dfa_minimization_reduces_states() {
+    let ab = || Concat(b(Byte(b'a')), b(Byte(b'b')));
+    let cb = || Concat(b(Byte(b'c')), b(Byte(b'b')));
     let cases: Vec<Regex> = vec![
-        // a*
-        Star(b(Byte(b'a'))),
-        // (a|b)*
-        Star(b(Alt(b(Byte(b'a')), b(Byte(b'b'))))),
-        // (a|b|a)
-        Alt(b(Alt(b(Byte(b'a')), b(Byte(b'b')))), b(Byte(b'a'))),
-        // ((a|b)*)*
-        Star(b(Star(b(Alt(b(Byte(b'a')), b(Byte(b'b'))))))),
+        // (ab|cb)
+        Alt(b(ab()), b(cb())),
+        // (ab|cb)*
+        Star(b(Alt(b(ab()), b(cb())))),
+        // (ab|cb|ab)
+        Alt(b(Alt(b(ab()), b(cb()))), b(ab())),
     ];
 
     for pattern in cases {
@@ -128,11 +131,11 @@ fn test_dfa_minimization_reduces_states() {
         let original = compiler::compile(&pattern, false);
 
         assert!(
-            minimized.states.len() < original.states.len(),
+            minimized.state_count() < original.state_count(),
             "Expected minimized DFA to shrink for pattern '{}': {} → {}",
             pattern,
-            original.states.len(),
-            minimized.states.len()
+            original.state_count(),
+            minimized.state_count()
         );
     }
 }
@@ -140,6 +143,14 @@ fn test_dfa_minimization_reduces_states() {
 #[test]
 fn test_dfa_minimization_preserves_state_count() {
     let cases: Vec<Regex> = vec![
+        // a*
+        Star(b(Byte(b'a'))),
+        // (a|b)*
+        Star(b(Alt(b(Byte(b'a')), b(Byte(b'b'))))),
+        // (a|b|a)
+        Alt(b(Alt(b(Byte(b'a')), b(Byte(b'b')))), b(Byte(b'a'))),
+        // ((a|b)*)*
+        Star(b(Star(b(Alt(b(Byte(b'a')), b(Byte(b'b'))))))),
         // (a|a)
         Alt(b(Byte(b'a')), b(Byte(b'a'))),
         // (ab|ab)
@@ -154,10 +165,111 @@ fn test_dfa_minimization_preserves_state_count() {
         let original = compiler::compile(&pattern, false);
 
         assert_eq!(
-            minimized.states.len(),
-            original.states.len(),
+            minimized.state_count(),
+            original.state_count(),
             "Expected no change in DFA size for pattern '{}'",
             pattern
         );
     }
 }
+
+#[test]
+fn test_dfa_uses_byte_classes() {
+    // a, b, c and everything else
+    let dfa = compiler::compile(&parse("(a|b)*c"), true);
+    assert_eq!(dfa.classes.count(), 4);
+    assert_eq!(dfa.classes.get(b'x'), dfa.classes.get(0xFF));
+    for state in 0..dfa.state_count() {
+        assert_eq!(
+            dfa.next(state, dfa.classes.get(b'x')),
+            dfa.next(state, dfa.classes.get(0xFF))
+        );
+    }
+
+    // . matches every byte the same way
+    let dfa = compiler::compile(&parse("a.b"), true);
+    assert_eq!(dfa.classes.count(), 3);
+}
+
+#[test]
+fn test_dfa_has_explicit_dead_state() {
+    let dfa = compiler::compile(&parse("ab"), true);
+    // dead, start, after a, after ab
+    assert_eq!(dfa.state_count(), 4);
+    assert_ne!(dfa.start(), DEAD);
+    for class in 0..dfa.classes.count() {
+        assert_eq!(dfa.next(DEAD, class), DEAD);
+    }
+    assert_eq!(dfa.next(dfa.start(), dfa.classes.get(b'b')), DEAD);
+    assert!(!dfa.accepting.contains(DEAD));
+}
+
+#[test]
+fn test_dfa_minimization_is_exact() {
+    // (a|b)*a(a|b){n} must remember the last n + 1 bytes: 2^(n+1) states
+    // plus the dead state, which the subset construction already reaches
+    for n in [3, 10] {
+        let pattern = format!("(a|b)*a{}", "(a|b)".repeat(n));
+        let dfa = compiler::compile(&parse(&pattern), true);
+        assert_eq!(dfa.state_count(), (1 << (n + 1)) + 1, "{}", pattern);
+    }
+
+    // every state of (a|b|c)*(a|b)* accepts and loops, so only one is left
+    let dfa = compiler::compile(&parse("(a|b|c)*(a|b)*"), true);
+    assert_eq!(dfa.state_count(), 2);
+}
+
+#[test]
+fn test_dfa_accelerates_self_looping_states() {
+    // after the a, every byte but z loops
+    let dfa = compiler::compile(&parse("a.*z"), true);
+    let middle = dfa.next(dfa.start(), dfa.classes.get(b'a'));
+    assert!(dfa.is_accelerated(middle));
+    assert!(!dfa.is_accelerated(dfa.start()));
+    assert!(!dfa.is_accelerated(DEAD));
+
+    let long = "a".to_string() + &"b".repeat(10_000);
+    assert!(dfa.matches(&(long.clone() + "z")));
+    assert!(dfa.matches(&(long.clone() + "zbz")));
+    assert!(!dfa.matches(&long));
+    assert!(!dfa.matches(&(long.clone() + "zb")));
+    assert_eq!(
+        dfa.longest_match((long.clone() + "zbbzb").as_bytes()),
+        Some(10_005)
+    );
+    assert_eq!(dfa.longest_match(long.as_bytes()), None);
+
+    let rev = compiler::compile_reverse(&parse("a.*z"), &Limits::default()).unwrap();
+    let haystack = "xx".to_string() + &long + "z";
+    assert_eq!(rev.longest_match_rev(haystack.as_bytes()), Some(10_002));
+
+    let re = AdamRegex::from_str("a.*z").unwrap();
+    let haystack = "x".repeat(100) + &long + "zbb";
+    let m = re.find(&haystack).unwrap();
+    assert_eq!(m.range(), 100..10_102);
+}
+
+#[test]
+fn test_dfa_classifies_dead_and_universal_states() {
+    let dfa = compiler::compile(&parse("abc.*"), true);
+    assert_eq!(dfa.kind(DEAD), StateKind::Dead);
+    assert_eq!(dfa.kind(dfa.start()), StateKind::Normal);
+    let after = "abc"
+        .bytes()
+        .fold(dfa.start(), |s, b| dfa.next(s, dfa.classes.get(b)));
+    assert_eq!(dfa.kind(after), StateKind::Universal);
+
+    let long = "abc".to_string() + &"x".repeat(10_000);
+    assert!(dfa.matches(&long));
+    assert!(!dfa.matches(&("abd".to_string() + &long)));
+    assert_eq!(dfa.longest_match(long.as_bytes()), Some(long.len()));
+    assert_eq!(dfa.shortest_match(long.as_bytes()), Some(3));
+    assert_eq!(dfa.shortest_match(b"abx"), None);
+
+    // a state that can still reach a rejecting one is not universal
+    let dfa = compiler::compile(&parse("a(b|c)*"), true);
+    let after = dfa.next(dfa.start(), dfa.classes.get(b'a'));
+    assert_eq!(dfa.kind(after), StateKind::Normal);
+    assert_eq!(dfa.shortest_match(b"abcbd"), Some(1));
+    assert_eq!(dfa.longest_match(b"abcbd"), Some(4));
+}
@@ -0,0 +1,48 @@
+use adam_regex::ast::Regex;
+use adam_regex::engine::compiler::{self, Construction};
+use adam_regex::error::Limits;
+use adam_regex::parser::parser::parse;
+
+mod common;
+
+fn check(ast: &Regex) {
+    let dfa = compiler::compile(ast, true);
+    for construction in [Construction::Thompson, Construction::Glushkov] {
+        let nfa = compiler::compile_nfa(ast, construction, &Limits::default()).unwrap();
+        // and a multibyte char, for classes of them
+        for input in common::inputs().iter().chain(&["é".to_string()]) {
+            assert_eq!(
+                nfa.matches(input),
+                dfa.matches(input),
+                "{} ({:?}) on {:?}",
+                ast,
+                construction,
+                input
+            );
+            assert_eq!(
+                nfa.shortest_match(input.as_bytes()),
+                dfa.shortest_match(input.as_bytes()),
+                "{} ({:?}) on {:?}",
+                ast,
+                construction,
+                input
+            );
+        }
+    }
+}
+
+#[test]
+fn agrees_with_dfa() {
+    for pattern in common::PATTERNS {
+        check(&parse(pattern));
+    }
+}
+
+#[test]
+fn agrees_with_dfa_on_built_patterns() {
+    check(&Regex::literal(""));
+    check(&Regex::never());
+    check(&Regex::any_char().plus());
+    check(&Regex::class_of(['a'..='b', 'é'..='é']).repeat(1..3));
+    check(&Regex::literal_ignore_case("Ab").star());
+}
@@ -0,0 +1,203 @@
+use std::io::{self, Read};
+
+use adam_regex::ast::Regex;
+use adam_regex::error::Limits;
+use adam_regex::matcher::AdamRegex;
+use adam_regex::parser::Dialect;
+
+mod common;
+
+/// Hands out at most `step` bytes per read, then fails if `fail` is set.
+struct Trickle<'a> {
+    input: &'a [u8],
+    step: usize,
+    fail: bool,
+}
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.input.is_empty() && self.fail {
+            return Err(io::Error::other("broken pipe"));
+        }
+        let n = self.step.min(buf.len()).min(self.input.len());
+        buf[..n].copy_from_slice(&self.input[..n]);
+        self.input = &self.input[n..];
+        Ok(n)
+    }
+}
+
+fn trickle(input: &[u8], step: usize) -> Trickle<'_> {
+    Trickle {
+        input,
+        step,
+        fail: false,
+    }
+}
+
+/// `pattern` over and over, `len` bytes in all.
+struct Repeat {
+    pattern: &'static [u8],
+    at: usize,
+    len: usize,
+}
+
+impl Read for Repeat {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.len - self.at);
+        for (i, b) in buf[..n].iter_mut().enumerate() {
+            *b = self.pattern[(self.at + i) % self.pattern.len()];
+        }
+        self.at += n;
+        Ok(n)
+    }
+}
+
+#[test]
+fn agrees_with_find_iter_however_the_stream_is_read() {
+    let small = Limits {
+        max_dfa_states: 4,
+        ..Limits::default()
+    };
+    let ab = Regex::literal("a").or("b");
+    let nth_from_end = ab.clone().star().then("a").then(ab.repeat(3..=3));
+    let engines = [
+        AdamRegex::from_str("ab*c").unwrap(),
+        AdamRegex::from_str("a*").unwrap(),
+        AdamRegex::from_str("(ab|a)(bc|c)?").unwrap(),
+        AdamRegex::from_str("b.*c").unwrap(),
+        AdamRegex::from_str("abc*").unwrap(),
+        AdamRegex::builder().lazy(true).build("(a|b)*c").unwrap(),
+        AdamRegex::builder()
+            .dialect(Dialect::Ere)
+            .build("^ab|c$|b")
+            .unwrap(),
+        // bit-parallel and then the NFA
+        AdamRegex::builder()
+            .limits(small.clone())
+            .build_ast(&nth_from_end)
+            .unwrap(),
+        AdamRegex::builder()
+            .limits(small)
+            .build_ast(&Regex::literal("c").or(nth_from_end.repeat(20..=20)))
+            .unwrap(),
+    ];
+
+    for re in &engines {
+        for input in common::inputs() {
+            let expected: Vec<_> = re.find_iter(&input).map(|m| m.range()).collect();
+            for (step, capacity) in [(1, 1), (2, 3), (64, 2), (64, 1024)] {
+                let found: Vec<_> = re
+                    .find_iter_reader(trickle(input.as_bytes(), step))
+                    .capacity(capacity)
+                    .map(|m| m.unwrap().range())
+                    .collect();
+                assert_eq!(found, expected, "{:?} by {}/{}", input, step, capacity);
+            }
+        }
+    }
+}
+
+#[test]
+fn returns_matched_bytes_across_refills() {
+    let re = AdamRegex::from_str("a(b|c)*d").unwrap();
+    let input = b"xxabcbcbdyyadzzabbb";
+    let found: Vec<_> = re
+        .find_iter_reader(trickle(input, 1))
+        .capacity(2)
+        .with_bytes()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0].range(), 2..9);
+    assert_eq!(found[0].bytes(), Some(&b"abcbcbd"[..]));
+    assert_eq!(found[1].range(), 11..13);
+    assert_eq!(found[1].bytes(), Some(&b"ad"[..]));
+
+    let m = re.find_iter_reader(&input[..]).next().unwrap().unwrap();
+    assert_eq!(m.bytes(), None);
+}
+
+#[test]
+fn long_streams_and_long_matches() {
+    // far more than is buffered at once, with matches everywhere
+    let re = AdamRegex::from_str("ab*c").unwrap();
+    let reader = Repeat {
+        pattern: b"abbbbc.",
+        at: 0,
+        len: 7 * 100_000,
+    };
+    let mut count = 0;
+    for (i, m) in re.find_iter_reader(reader).capacity(100).enumerate() {
+        assert_eq!(m.unwrap().range(), 7 * i..7 * i + 6);
+        count += 1;
+    }
+    assert_eq!(count, 100_000);
+
+    // one match spanning the whole stream
+    let re = AdamRegex::from_str("x.*").unwrap();
+    let reader = Repeat {
+        pattern: b"xyz",
+        at: 0,
+        len: 1 << 20,
+    };
+    let mut found = re.find_iter_reader(reader).capacity(16);
+    assert_eq!(found.next().unwrap().unwrap().range(), 0..1 << 20);
+    assert!(found.next().is_none());
+}
+
+#[test]
+fn read_errors_end_the_iteration() {
+    let re = AdamRegex::from_str("ab").unwrap();
+    let reader = Trickle {
+        input: b"abxab",
+        step: 2,
+        fail: true,
+    };
+    let mut found = re.find_iter_reader(reader);
+    assert_eq!(found.next().unwrap().unwrap().range(), 0..2);
+    // the second match is only known to end once the next read succeeds
+    assert!(found.next().unwrap().is_err());
+    assert!(found.next().is_none());
+}
+
+#[test]
+fn failed_candidates_keep_no_bytes() {
+    // every a starts a candidate that runs to the end of the stream, so
+    // searching from each of them in turn would take quadratic time
+    let re = AdamRegex::from_str("a.*b").unwrap();
+    let reader = Repeat {
+        pattern: b"a",
+        at: 0,
+        len: 1 << 20,
+    };
+    let mut found = re.find_iter_reader(reader).capacity(256).buffer_limit(1024);
+    assert!(found.next().is_none());
+}
+
+#[test]
+fn keeping_too_many_bytes_is_an_error() {
+    let re = AdamRegex::from_str("a.*b").unwrap();
+    let reader = Repeat {
+        pattern: b"a",
+        at: 0,
+        len: 1 << 16,
+    };
+    let mut found = re
+        .find_iter_reader(reader)
+        .capacity(256)
+        .buffer_limit(1024)
+        .with_bytes();
+    let err = found.next().unwrap().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::OutOfMemory);
+    assert!(found.next().is_none());
+
+    // the bytes after a match are kept while it can still grow
+    let re = AdamRegex::from_str("a|a.*b").unwrap();
+    let reader = Repeat {
+        pattern: b"a",
+        at: 0,
+        len: 1 << 16,
+    };
+    let mut found = re.find_iter_reader(reader).capacity(256).buffer_limit(1024);
+    assert!(found.next().unwrap().is_err());
+}
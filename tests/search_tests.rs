@@ -0,0 +1,429 @@
+use adam_regex::engine::compiler;
+use adam_regex::error::Limits;
+use adam_regex::matcher::AdamRegex;
+use adam_regex::parser::parser::parse;
+use adam_regex::parser::Dialect;
+
+mod common;
+
+/// Leftmost-longest match by trying every substring.
+fn naive(re: &AdamRegex, haystack: &str) -> Option<(usize, usize)> {
+    (0..=haystack.len()).find_map(|start| {
+        (start..=haystack.len())
+            .rev()
+            .find(|&end| re.matches(&haystack[start..end]))
+            .map(|end| (start, end))
+    })
+}
+
+/// Rightmost-longest match: the latest end, then the earliest start.
+fn naive_rev(re: &AdamRegex, haystack: &str) -> Option<(usize, usize)> {
+    (0..=haystack.len()).rev().find_map(|end| {
+        (0..=end)
+            .find(|&start| re.matches(&haystack[start..end]))
+            .map(|start| (start, end))
+    })
+}
+
+/// For every end, the longest match ending there.
+fn naive_overlapping(re: &AdamRegex, haystack: &str) -> Vec<(usize, usize)> {
+    (0..=haystack.len())
+        .filter_map(|end| {
+            (0..=end)
+                .find(|&start| re.matches(&haystack[start..end]))
+                .map(|start| (start, end))
+        })
+        .collect()
+}
+
+#[test]
+fn find_is_leftmost_longest() {
+    let haystacks = common::inputs();
+    for pattern in [
+        "ab",
+        "abc",
+        "a|b",
+        "(ab|ac)c*",
+        "b+",
+        "a*",
+        "ca?b",
+        "a.c",
+        "(a|b)*c",
+        "abc|b",
+        "ab*c|b",
+        "a*(b|c)",
+        "(a|b)*(c|ab)",
+    ] {
+        let re = AdamRegex::from_str(pattern).unwrap();
+        // and with threads, past a budget too small for the DFAs
+        let threads = AdamRegex::builder()
+            .limits(Limits {
+                max_dfa_states: 2,
+                ..Limits::default()
+            })
+            .build(pattern)
+            .unwrap();
+        for haystack in &haystacks {
+            let found = re.find(haystack).map(|m| (m.start(), m.end()));
+            assert_eq!(found, naive(&re, haystack), "{} in {:?}", pattern, haystack);
+            assert_eq!(re.is_match(haystack), found.is_some());
+            let found_by_threads = threads.find(haystack).map(|m| (m.start(), m.end()));
+            assert_eq!(found_by_threads, found, "{} in {:?}", pattern, haystack);
+        }
+    }
+}
+
+#[test]
+fn find_is_linear_in_the_haystack() {
+    // Trying each start in turn would read the rest of the haystack from
+    // every one of them
+    let haystack = "a".repeat(100_000);
+    for pattern in ["a*(b|c)", "(a|b)*(c|d)"] {
+        let re = AdamRegex::from_str(pattern).unwrap();
+        assert!(re.find(&haystack).is_none(), "{}", pattern);
+        assert!(!re.is_match(&haystack), "{}", pattern);
+        assert_eq!(
+            re.find(&(haystack.clone() + "c")).map(|m| m.len()),
+            Some(100_001)
+        );
+    }
+}
+
+#[test]
+fn find_iter_is_non_overlapping() {
+    let re = AdamRegex::from_str("ab+").unwrap();
+    let matches: Vec<_> = re.find_iter("xabbbyabzab").map(|m| m.range()).collect();
+    assert_eq!(matches, vec![1..5, 6..8, 9..11]);
+
+    let re = AdamRegex::from_str("a*").unwrap();
+    let matches: Vec<_> = re.find_iter("baab").map(|m| m.range()).collect();
+    assert_eq!(matches, vec![0..0, 1..3, 3..3, 4..4]);
+}
+
+#[test]
+fn literal_prefix_search() {
+    let re = AdamRegex::builder()
+        .dialect(Dialect::Ere)
+        .build("ERROR: [a-z]+")
+        .unwrap();
+    let log = "INFO: ok\n".repeat(100) + "ERROR: disk full\nERROR: again";
+
+    let m = re.find(&log).unwrap();
+    assert_eq!(m.as_bytes(), b"ERROR: disk");
+    assert_eq!(m.start(), 900);
+
+    let all: Vec<_> = re.find_iter(&log).map(|m| m.as_bytes()).collect();
+    assert_eq!(all, vec![&b"ERROR: disk"[..], b"ERROR: again"]);
+    assert!(!re.is_match("INFO: ERROR:"));
+}
+
+#[test]
+fn search_with_every_engine() {
+    let ab = "(a|b)*a".to_string() + &"(a|b)".repeat(12);
+    let lazy = AdamRegex::builder().lazy(true).build(&ab).unwrap();
+    let bits = AdamRegex::from_str(&ab).unwrap();
+    let haystack = "c".repeat(5) + &"a".repeat(13) + "c";
+    for re in [&lazy, &bits] {
+        assert_eq!(re.find(&haystack).map(|m| m.range()), Some(5..18));
+    }
+}
+
+#[test]
+fn inner_literal_search() {
+    let re = AdamRegex::builder()
+        .dialect(Dialect::Ere)
+        .build("[a-z]+@example[.]com")
+        .unwrap();
+    let text = "mail bob@example.org or alice@example.com, not eve@example.co";
+    let all: Vec<_> = re.find_iter(text).map(|m| m.as_bytes()).collect();
+    assert_eq!(all, vec![&b"alice@example.com"[..]]);
+
+    let re = AdamRegex::from_str("(a|b)*z").unwrap();
+    assert!(re.find(&"ab".repeat(1000)).is_none());
+    let m = re.find("cabzabz").unwrap();
+    assert_eq!(m.range(), 1..4);
+}
+
+#[test]
+fn inner_literals_only_come_from_a_concatenation() {
+    // each branch of a top-level alternation is a match on its own
+    let re = AdamRegex::from_str(".|c").unwrap();
+    assert_eq!(re.find("x").map(|m| m.range()), Some(0..1));
+    assert!(re.is_match("x"));
+    for pattern in ["a*|b", "x*|a|b"] {
+        let re = AdamRegex::from_str(pattern).unwrap();
+        assert_eq!(re.find("a").map(|m| m.range()), Some(0..1), "{}", pattern);
+    }
+
+    // and a prefix that can run over the literal may start a match earlier
+    let haystacks = common::inputs();
+    for pattern in [
+        ".|c",
+        "a*|b",
+        "x*|a|b",
+        "a*bc|b",
+        "(a|b)*ab",
+        "c*ab",
+        "b(a|c)*ca",
+    ] {
+        let re = AdamRegex::from_str(pattern).unwrap();
+        for haystack in &haystacks {
+            let found = re.find(haystack).map(|m| (m.start(), m.end()));
+            assert_eq!(found, naive(&re, haystack), "{} in {:?}", pattern, haystack);
+            assert_eq!(re.is_match(haystack), found.is_some());
+        }
+    }
+}
+
+#[test]
+fn rfind_is_rightmost_longest() {
+    let haystacks = common::inputs();
+    for pattern in [
+        "ab",
+        "a|b",
+        "(ab|ac)c*",
+        "b+",
+        "a*",
+        "ca?b",
+        "a.c",
+        "(a|b)*c",
+    ] {
+        let re = AdamRegex::from_str(pattern).unwrap();
+        for haystack in &haystacks {
+            let found = re.rfind(haystack).map(|m| (m.start(), m.end()));
+            assert_eq!(
+                found,
+                naive_rev(&re, haystack),
+                "{} in {:?}",
+                pattern,
+                haystack
+            );
+        }
+    }
+}
+
+#[test]
+fn rfind_finds_the_last_timestamp() {
+    let re = AdamRegex::builder()
+        .dialect(Dialect::Ere)
+        .build("[0-9]{2}:[0-9]{2}:[0-9]{2}")
+        .unwrap();
+    let log = "12:00:01 start\n".to_string() + &"noise\n".repeat(1000) + "23:59:59 stop\n";
+    assert_eq!(re.rfind(&log).unwrap().as_bytes(), b"23:59:59");
+    assert_eq!(re.find(&log).unwrap().as_bytes(), b"12:00:01");
+    assert!(re.rfind("no time here").is_none());
+}
+
+#[test]
+fn rfind_without_a_reverse_dfa() {
+    // too few DFA states for either direction, so NFA threads find it
+    let re = AdamRegex::builder()
+        .limits(Limits {
+            max_dfa_states: 2,
+            ..Limits::default()
+        })
+        .build("(ab|ac)c*")
+        .unwrap();
+    for haystack in common::inputs() {
+        let found = re.rfind(&haystack).map(|m| (m.start(), m.end()));
+        assert_eq!(found, naive_rev(&re, &haystack), "{:?}", haystack);
+    }
+}
+
+#[test]
+fn rfind_is_linear() {
+    // a b could start a match anywhere up to the end, so trying every end
+    // and reading back from it would take quadratic time
+    let haystack = "a".repeat(200_000);
+    let nfa_only = Limits {
+        max_dfa_states: 2,
+        ..Limits::default()
+    };
+    for limits in [Limits::default(), nfa_only] {
+        let re = AdamRegex::builder().limits(limits).build("ba*").unwrap();
+        assert!(re.rfind(&haystack).is_none());
+        let hit = "b".to_string() + &haystack;
+        assert_eq!(re.rfind(&hit).unwrap().range(), 0..hit.len());
+    }
+}
+
+#[test]
+fn reverse_dfa_matches_reversed_strings() {
+    for pattern in ["ab", "(ab|ac)c*", "a(b|cd)*e?", "a.c"] {
+        let ast = parse(pattern);
+        let forward = compiler::compile(&ast, true);
+        let reverse = compiler::compile_reverse(&ast, &Limits::default()).unwrap();
+        for haystack in common::inputs() {
+            let reversed: String = haystack.chars().rev().collect();
+            assert_eq!(forward.matches(&haystack), reverse.matches(&reversed));
+        }
+    }
+}
+
+#[test]
+fn overlapping_matches_share_bytes() {
+    let re = AdamRegex::from_str("ab*a").unwrap();
+    let all: Vec<_> = re
+        .find_overlapping_iter("abaabbaxaba")
+        .map(|m| m.range())
+        .collect();
+    assert_eq!(all, vec![0..3, 2..4, 3..7, 8..11]);
+
+    let re = AdamRegex::from_str("aba").unwrap();
+    let all: Vec<_> = re
+        .find_overlapping_iter("ababa")
+        .map(|m| m.range())
+        .collect();
+    assert_eq!(all, vec![0..3, 2..5]);
+}
+
+#[test]
+fn overlapping_matches_agree_with_naive_search() {
+    let small = Limits {
+        max_dfa_states: 2,
+        ..Limits::default()
+    };
+    for pattern in [
+        "ab",
+        "a|b",
+        "(ab|ac)c*",
+        "b+",
+        "a*",
+        "ca?b",
+        "a.c",
+        "(a|b)*c",
+    ] {
+        let re = AdamRegex::from_str(pattern).unwrap();
+        // without room for any DFA, every direction runs on the NFA
+        let nfa = AdamRegex::builder()
+            .limits(small.clone())
+            .build(pattern)
+            .unwrap();
+        for haystack in common::inputs() {
+            let expected = naive_overlapping(&re, &haystack);
+            for re in [&re, &nfa] {
+                let found: Vec<_> = re
+                    .find_overlapping_iter(&haystack)
+                    .map(|m| (m.start(), m.end()))
+                    .collect();
+                assert_eq!(found, expected, "{} in {:?}", pattern, haystack);
+            }
+        }
+    }
+}
+
+#[test]
+fn overlapping_search_is_linear() {
+    // read backwards from any end, the pattern could still be waiting for
+    // an x, so searching back from every end would take quadratic time
+    let re = AdamRegex::from_str("a|xa*a").unwrap();
+    let haystack = "a".repeat(200_000);
+    let mut count = 0;
+    for (i, m) in re.find_overlapping_iter(&haystack).enumerate() {
+        assert_eq!(m.range(), i..i + 1);
+        count += 1;
+    }
+    assert_eq!(count, haystack.len());
+}
+
+#[test]
+fn ere_anchors_hold_in_search() {
+    let ere = |pattern| {
+        AdamRegex::builder()
+            .dialect(Dialect::Ere)
+            .build(pattern)
+            .unwrap()
+    };
+    let re = ere("^foo");
+    assert!(re.find("xfoo").is_none());
+    assert!(!re.is_match("xfoo"));
+    assert_eq!(re.find("foox").unwrap().range(), 0..3);
+
+    let re = ere("foo$");
+    assert!(re.find("foox").is_none());
+    assert!(re.rfind("foox").is_none());
+    assert_eq!(re.find("foofoo").unwrap().range(), 3..6);
+
+    let re = ere("^$");
+    assert_eq!(re.find("").unwrap().range(), 0..0);
+    assert!(re.find("a").is_none());
+
+    let re = ere("^a|b$|c");
+    let all: Vec<_> = re.find_iter("aacabcb").map(|m| m.range()).collect();
+    assert_eq!(all, vec![0..1, 2..3, 5..6, 6..7]);
+
+    // an empty match at the end is the last one
+    for (pattern, haystack, end) in [("a*$", "b", 1), ("b*$", "a", 1), ("^a|b*", "", 0)] {
+        let all: Vec<_> = ere(pattern)
+            .find_iter(haystack)
+            .map(|m| m.range())
+            .collect();
+        assert_eq!(
+            all.last(),
+            Some(&(end..end)),
+            "{} in {:?}",
+            pattern,
+            haystack
+        );
+    }
+    // anchors still only matter to search
+    assert!(re.matches("b"));
+}
+
+#[test]
+fn anchored_search_agrees_with_naive_search() {
+    let ere = |pattern: &str| {
+        AdamRegex::builder()
+            .dialect(Dialect::Ere)
+            .build(pattern)
+            .unwrap()
+    };
+    for branches in [
+        &["^ab"][..],
+        &["b*$"],
+        &["^a*$"],
+        &["^(ab|a)c?$", "b"],
+        &["^a", "b$", "(a|c)b*"],
+        &["^$", "c"],
+    ] {
+        let pattern = branches.join("|");
+        let re = ere(&pattern);
+        // whole-input matching ignores the anchors
+        let unanchored: Vec<_> = branches.iter().map(|branch| ere(branch)).collect();
+        for haystack in common::inputs() {
+            let len = haystack.len();
+            let is_match = |start: usize, end: usize| {
+                branches.iter().zip(&unanchored).any(|(branch, re)| {
+                    (start == 0 || !branch.starts_with('^'))
+                        && (end == len || !branch.ends_with('$'))
+                        && re.matches(&haystack[start..end])
+                })
+            };
+            let leftmost = (0..=len).find_map(|start| {
+                (start..=len)
+                    .rev()
+                    .find(|&end| is_match(start, end))
+                    .map(|end| (start, end))
+            });
+            let overlapping: Vec<_> = (0..=len)
+                .filter_map(|end| {
+                    (0..=end)
+                        .find(|&start| is_match(start, end))
+                        .map(|start| (start, end))
+                })
+                .collect();
+
+            let context = format!("{} in {:?}", pattern, haystack);
+            let found = re.find(&haystack).map(|m| (m.start(), m.end()));
+            assert_eq!(found, leftmost, "{}", context);
+            assert_eq!(re.is_match(&haystack), found.is_some(), "{}", context);
+            let last = re.rfind(&haystack).map(|m| (m.start(), m.end()));
+            assert_eq!(last, overlapping.last().copied(), "{}", context);
+            let found: Vec<_> = re
+                .find_overlapping_iter(&haystack)
+                .map(|m| (m.start(), m.end()))
+                .collect();
+            assert_eq!(found, overlapping, "{}", context);
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use adam_regex::engine::compiler;
+use adam_regex::engine::dfa::Priority;
+use adam_regex::error::{Error, Limits};
+use adam_regex::matcher::AdamRegex;
+use adam_regex::parser::parser::parse;
+use adam_regex::parser::Dialect;
+use adam_regex::set::RegexSet;
+
+mod common;
+
+#[test]
+fn reports_every_matching_pattern() {
+    let set = RegexSet::new(["ab", "a+", "(a|b)*", "c"]).unwrap();
+    assert_eq!(set.len(), 4);
+    assert_eq!(set.matches("ab"), vec![0, 2]);
+    assert_eq!(set.matches("aaa"), vec![1, 2]);
+    assert_eq!(set.matches(""), vec![2]);
+    assert_eq!(set.matches("c"), vec![3]);
+    assert!(set.matches("abc").is_empty());
+}
+
+#[test]
+fn agrees_with_separate_regexes() {
+    let patterns = common::PATTERNS;
+    let set = RegexSet::new(patterns).unwrap();
+    let regexes: Vec<_> = patterns
+        .iter()
+        .map(|p| AdamRegex::from_str(p).unwrap())
+        .collect();
+
+    for input in common::inputs() {
+        let expected: Vec<usize> = (0..patterns.len())
+            .filter(|&i| regexes[i].matches(&input))
+            .collect();
+        assert_eq!(set.matches(&input), expected, "{:?}", input);
+    }
+}
+
+#[test]
+fn duplicate_patterns_both_match() {
+    let set = RegexSet::new(["a|b", "b|a"]).unwrap();
+    assert_eq!(set.matches("b"), vec![0, 1]);
+
+    let empty = RegexSet::new(Vec::<&str>::new()).unwrap();
+    assert!(empty.is_empty());
+    assert!(empty.matches("").is_empty());
+}
+
+#[test]
+fn minimization_keeps_patterns_apart() {
+    let asts = [parse("a"), parse("b")];
+    let dfa = compiler::compile_set(&asts, &Limits::default()).unwrap();
+    // dead, start, and one accepting state per pattern; `a|b` needs only 3
+    assert_eq!(dfa.state_count(), 4);
+    assert_eq!(compiler::compile(&parse("a|b"), true).state_count(), 3);
+    assert_eq!(dfa.matching_patterns(b"b"), &[1]);
+}
+
+#[test]
+fn builder_settings_apply_to_every_pattern() {
+    let set = AdamRegex::builder()
+        .dialect(Dialect::Ere)
+        .build_set(["GET /[a-z]+", "POST /[a-z]+", "[A-Z]+ /login"])
+        .unwrap();
+    assert_eq!(set.matches("GET /login"), vec![0, 2]);
+    assert_eq!(set.matches("PUT /login"), vec![2]);
+
+    assert!(matches!(RegexSet::new(["a", "(b"]), Err(Error::Syntax(_))));
+    let anchored = AdamRegex::builder()
+        .dialect(Dialect::Ere)
+        .build_set(["GET", "^POST"]);
+    assert!(matches!(anchored, Err(Error::Syntax(_))));
+
+    let many: Vec<String> = (0..40)
+        .map(|i| format!("(a|b)*a{}", "(a|b)".repeat(i % 12)))
+        .collect();
+    let err = AdamRegex::builder()
+        .limits(Limits {
+            max_dfa_states: 100,
+            ..Limits::default()
+        })
+        .build_set(&many)
+        .unwrap_err();
+    assert_eq!(err, Error::TooManyDfaStates { limit: 100 });
+}
+
+/// The leftmost match at or after `at` by trying every substring against
+/// every pattern.
+fn naive(
+    regexes: &[AdamRegex],
+    priority: Priority,
+    haystack: &str,
+) -> Option<(usize, usize, usize)> {
+    (0..=haystack.len()).find_map(|start| {
+        let longest = |id: usize| {
+            (start..=haystack.len())
+                .rev()
+                .find(|&end| regexes[id].matches(&haystack[start..end]))
+        };
+        let ids = 0..regexes.len();
+        match priority {
+            Priority::Longest => ids
+                .filter_map(|id| longest(id).map(|end| (end, id)))
+                .min_by_key(|&(end, id)| (std::cmp::Reverse(end), id))
+                .map(|(end, id)| (id, start, end)),
+            Priority::FirstPattern => ids
+                .into_iter()
+                .find_map(|id| longest(id).map(|end| (id, start, end))),
+        }
+    })
+}
+
+#[test]
+fn find_agrees_with_naive_search() {
+    let patterns = ["ab", "a+", "b(a|c)*", "ca?b", "cc"];
+    let regexes: Vec<_> = patterns
+        .iter()
+        .map(|p| AdamRegex::from_str(p).unwrap())
+        .collect();
+    // Tight budgets leave the search DFAs to NFA threads
+    for max_dfa_states in (1..=64).chain([Limits::default().max_dfa_states]) {
+        let limits = Limits {
+            max_dfa_states,
+            ..Limits::default()
+        };
+        for priority in [Priority::Longest, Priority::FirstPattern] {
+            let builder = AdamRegex::builder()
+                .priority(priority)
+                .limits(limits.clone());
+            let Ok(set) = builder.build_set(patterns) else {
+                continue;
+            };
+            for haystack in common::inputs() {
+                let found = set
+                    .find(&haystack)
+                    .map(|m| (m.pattern_id(), m.start(), m.end()));
+                assert_eq!(
+                    found,
+                    naive(&regexes, priority, &haystack),
+                    "{:?} in {:?}",
+                    priority,
+                    haystack
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn find_is_linear_in_the_haystack() {
+    let set = RegexSet::new(["a*(b|c)", "(a|b)*d", "x"]).unwrap();
+    let haystack = "a".repeat(100_000);
+    assert!(set.find(&haystack).is_none());
+    let m = set
+        .find(&(haystack + "d"))
+        .map(|m| (m.pattern_id(), m.len()));
+    assert_eq!(m, Some((1, 100_001)));
+}
+
+#[test]
+fn find_iter_reports_pattern_ids() {
+    let patterns = ["ab", "abcd", "cd+", "x"];
+    let haystack = "zabcdxcddab";
+
+    let set = RegexSet::new(patterns).unwrap();
+    let found: Vec<_> = set
+        .find_iter(haystack)
+        .map(|m| (m.pattern_id(), m.as_bytes()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![(1, &b"abcd"[..]), (3, b"x"), (2, b"cdd"), (0, b"ab")]
+    );
+
+    let set = AdamRegex::builder()
+        .priority(Priority::FirstPattern)
+        .build_set(patterns)
+        .unwrap();
+    let found: Vec<_> = set
+        .find_iter(haystack)
+        .map(|m| (m.pattern_id(), m.as_bytes()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (0, &b"ab"[..]),
+            (2, b"cd"),
+            (3, b"x"),
+            (2, b"cdd"),
+            (0, b"ab")
+        ]
+    );
+
+    // a single regex always reports pattern 0
+    let re = AdamRegex::from_str("cd+").unwrap();
+    assert!(re.find_iter(haystack).all(|m| m.pattern_id() == 0));
+}
+
+#[test]
+fn overlapping_matches_of_every_pattern() {
+    let set = RegexSet::new(["ab", "b+", "abb", "a.*"]).unwrap();
+    let found: Vec<_> = set
+        .find_overlapping_iter("abbab")
+        .map(|m| (m.pattern_id(), m.range()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (3, 0..1),
+            (0, 0..2),
+            (1, 1..2),
+            (3, 0..2),
+            (1, 1..3),
+            (2, 0..3),
+            (3, 0..3),
+            (3, 0..4),
+            (0, 3..5),
+            (1, 4..5),
+            (3, 0..5),
+        ]
+    );
+
+    let patterns = ["(a|b)*c", "ca?b", "b", "a*"];
+    let set = RegexSet::new(patterns).unwrap();
+    let regexes: Vec<_> = patterns
+        .iter()
+        .map(|p| AdamRegex::from_str(p).unwrap())
+        .collect();
+    for haystack in common::inputs() {
+        let mut expected = Vec::new();
+        for end in 0..=haystack.len() {
+            for (id, re) in regexes.iter().enumerate() {
+                if let Some(start) = (0..=end).find(|&s| re.matches(&haystack[s..end])) {
+                    expected.push((id, start, end));
+                }
+            }
+        }
+        let found: Vec<_> = set
+            .find_overlapping_iter(&haystack)
+            .map(|m| (m.pattern_id(), m.start(), m.end()))
+            .collect();
+        assert_eq!(found, expected, "{:?}", haystack);
+    }
+}
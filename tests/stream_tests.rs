@@ -0,0 +1,118 @@
+use adam_regex::ast::Regex;
+use adam_regex::error::Limits;
+use adam_regex::matcher::AdamRegex;
+
+mod common;
+
+/// Feeds `input` split at `cuts`.
+fn stream(re: &AdamRegex, input: &str, cuts: &[usize]) -> bool {
+    let mut matcher = re.matcher();
+    let mut from = 0;
+    for &cut in cuts.iter().chain([&input.len()]) {
+        matcher.feed(&input.as_bytes()[from..cut]);
+        from = cut;
+    }
+    matcher.finish()
+}
+
+/// `(a|b)*a(a|b){n}`, whose DFA needs 2^(n+1) states.
+fn nth_from_end(n: usize) -> Regex {
+    let ab = Regex::literal("a").or("b");
+    ab.clone().star().then("a").then(ab.repeat(n..=n))
+}
+
+#[test]
+fn chunk_boundaries_do_not_matter() {
+    let small = Limits {
+        max_dfa_states: 4,
+        ..Limits::default()
+    };
+    let ast = nth_from_end(3);
+    let engines = [
+        AdamRegex::from_ast(&ast),
+        AdamRegex::builder().lazy(true).build_ast(&ast).unwrap(),
+        // too many DFA states, so bit-parallel
+        AdamRegex::builder().limits(small).build_ast(&ast).unwrap(),
+        // too many positions for bit-parallel as well, so the NFA
+        AdamRegex::builder()
+            .limits(Limits {
+                max_dfa_states: 4,
+                ..Limits::default()
+            })
+            .build_ast(
+                &Regex::literal("b")
+                    .star()
+                    .then(nth_from_end(3).repeat(20..=20))
+                    .or(ast.clone()),
+            )
+            .unwrap(),
+    ];
+
+    for re in &engines {
+        for input in common::inputs() {
+            let expected = re.matches(&input);
+            for cut in 0..=input.len() {
+                assert_eq!(
+                    stream(re, &input, &[cut]),
+                    expected,
+                    "{:?} at {}",
+                    input,
+                    cut
+                );
+            }
+            let every_byte: Vec<usize> = (1..input.len()).collect();
+            assert_eq!(stream(re, &input, &every_byte), expected, "{:?}", input);
+        }
+
+        let mut matcher = re.matcher();
+        matcher.feed(b"ab");
+        assert!(!matcher.is_dead());
+        matcher.feed(b"c");
+        assert!(matcher.is_dead());
+    }
+}
+
+#[test]
+fn dead_streams_can_stop_early() {
+    let re = AdamRegex::from_str("ab*c").unwrap();
+    let mut matcher = re.matcher();
+    assert!(!matcher.is_dead());
+    matcher.feed(b"abbb");
+    assert!(!matcher.is_match());
+    assert!(!matcher.is_dead());
+    matcher.feed(b"c");
+    assert!(matcher.is_match());
+    matcher.feed(b"c");
+    assert!(matcher.is_dead());
+    assert!(!matcher.finish());
+
+    let re = AdamRegex::from_str("a*").unwrap();
+    let mut matcher = re.matcher();
+    assert!(matcher.is_match());
+    for _ in 0..1000 {
+        matcher.feed(&[b'a'; 4096]);
+    }
+    assert!(matcher.finish());
+}
+
+#[test]
+fn lazy_state_survives_cache_clears() {
+    let ast = nth_from_end(3);
+    let re = AdamRegex::builder()
+        .lazy(true)
+        .limits(Limits {
+            max_dfa_states: 2,
+            ..Limits::default()
+        })
+        .build_ast(&ast)
+        .unwrap();
+    for input in common::inputs() {
+        let mut matcher = re.matcher();
+        for (i, &byte) in input.as_bytes().iter().enumerate() {
+            matcher.feed(&[byte]);
+            // matching clears the regex's own cache in between
+            assert_eq!(re.matches(&input[..=i]), matcher.is_match(), "{:?}", input);
+        }
+        assert_eq!(matcher.finish(), re.matches(&input), "{:?}", input);
+    }
+}